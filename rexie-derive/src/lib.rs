@@ -0,0 +1,471 @@
+//! Derive macro for [`rexie::Record`](https://docs.rs/rexie/latest/rexie/trait.Record.html). Use it through the
+//! `derive` feature of `rexie` instead of depending on this crate directly.
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    ext::IdentExt, meta::ParseNestedMeta, parse_macro_input, spanned::Spanned, Attribute, Data,
+    DeriveInput, Error, Fields, LitStr, Result, Token,
+};
+
+/// Derives `rexie::Record` for a struct with named fields.
+///
+/// # Attributes
+///
+/// On the struct:
+///
+/// - `#[rexie(store = "name")]`: name of the object store (defaults to the name of the struct in snake case)
+///
+/// On the fields:
+///
+/// - `#[rexie(key)]`: the field is (part of) the key path of the object store. If more than one field is marked as
+///   key, the key path is an array of the fields in declaration order.
+/// - `#[rexie(key, auto_increment)]`: the object store auto increments the key (only for a single key field)
+/// - `#[rexie(index)]`: creates an index on the field (named after the field)
+/// - `#[rexie(index = "name")]`: creates an index on the field with given name
+/// - `#[rexie(index, unique)]` and `#[rexie(index, multi_entry)]`: flags of the index
+/// - `#[rexie(version)]`: the field holds the version of the record (see `rexie::ObjectStore::version_attribute`)
+///
+/// Key paths follow the serde names of the fields, i.e., `#[serde(rename = "..")]` on a field and
+/// `#[serde(rename_all = "..")]` on the struct are taken into account, so that they match the values written by
+/// `rexie::TypedStore`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(rexie::Record)]
+/// #[rexie(store = "employees")]
+/// struct Employee {
+///     #[rexie(key, auto_increment)]
+///     id: u32,
+///     name: String,
+///     #[rexie(index, unique)]
+///     email: String,
+/// }
+///
+/// let rexie = Rexie::builder("test")
+///     .add_object_store(Employee::object_store())
+///     .build()
+///     .await?;
+/// ```
+#[proc_macro_derive(Record, attributes(rexie))]
+pub fn derive_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Options of the struct
+struct StoreOptions {
+    name: String,
+    rename_all: Option<LitStr>,
+}
+
+/// Options of a field
+#[derive(Default)]
+struct FieldOptions {
+    key: bool,
+    auto_increment: Option<Span>,
+    index: Option<Option<String>>,
+    unique: Option<Span>,
+    multi_entry: Option<Span>,
+    version: Option<Span>,
+    rename: Option<String>,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "`Record` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`Record` can only be derived for structs",
+            ))
+        }
+    };
+
+    let store = parse_store_options(&input)?;
+
+    let mut key_fields = Vec::new();
+    let mut auto_increment = false;
+    let mut indexes = Vec::new();
+    let mut version_field = None;
+
+    for field in fields {
+        let options = parse_field_options(field)?;
+        let field_name = field
+            .ident
+            .as_ref()
+            .map(|ident| ident.unraw().to_string())
+            .unwrap_or_default();
+
+        // Name of the attribute holding the field in the serialized values
+        let attribute_name = match (options.rename, &store.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(rename_all)) => rename_field(&field_name, rename_all)?,
+            (None, None) => field_name.clone(),
+        };
+
+        if options.key {
+            key_fields.push(attribute_name.clone());
+            auto_increment |= options.auto_increment.is_some();
+        } else if let Some(span) = options.auto_increment {
+            return Err(Error::new(
+                span,
+                "`auto_increment` can only be used on a key field",
+            ));
+        }
+
+        if let Some(span) = options.version {
+            if version_field.is_some() {
+                return Err(Error::new(
+                    span,
+                    "`version` can only be used on a single field",
+                ));
+            }
+
+            version_field = Some(attribute_name.clone());
+        }
+
+        match options.index {
+            Some(index_name) => {
+                let index_name = index_name.unwrap_or_else(|| field_name.clone());
+                let unique = options.unique.is_some();
+                let multi_entry = options.multi_entry.is_some();
+
+                indexes.push(quote! {
+                    .add_index(
+                        ::rexie::Index::new(#index_name, #attribute_name)
+                            .unique(#unique)
+                            .multi_entry(#multi_entry)
+                    )
+                });
+            }
+            None => {
+                if let Some(span) = options.unique.or(options.multi_entry) {
+                    return Err(Error::new(
+                        span,
+                        "`unique` and `multi_entry` can only be used on an index field",
+                    ));
+                }
+            }
+        }
+    }
+
+    if auto_increment && key_fields.len() > 1 {
+        return Err(Error::new(
+            input.span(),
+            "`auto_increment` can't be used with a compound key",
+        ));
+    }
+
+    let key_path = match key_fields.as_slice() {
+        [] => quote! {},
+        [key_field] => quote! { .key_path(#key_field) },
+        key_fields => quote! { .key_path_array([#(#key_fields),*]) },
+    };
+
+    let version_attribute = version_field
+        .map(|version_field| quote! { .version_attribute(#version_field) })
+        .unwrap_or_default();
+
+    let ident = &input.ident;
+    let store_name = store.name;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rexie::Record for #ident #ty_generics #where_clause {
+            const STORE_NAME: &'static str = #store_name;
+
+            fn object_store() -> ::rexie::ObjectStore {
+                ::rexie::ObjectStore::new(#store_name)
+                    #key_path
+                    .auto_increment(#auto_increment)
+                    #(#indexes)*
+                    #version_attribute
+            }
+        }
+    })
+}
+
+fn parse_store_options(input: &DeriveInput) -> Result<StoreOptions> {
+    let mut name = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("rexie"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("store") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown `rexie` attribute on a struct, expected `store`"))
+            }
+        })?;
+    }
+
+    Ok(StoreOptions {
+        name: name.unwrap_or_else(|| to_snake_case(&input.ident.to_string())),
+        rename_all: serde_attribute(&input.attrs, "rename_all")?,
+    })
+}
+
+fn parse_field_options(field: &syn::Field) -> Result<FieldOptions> {
+    let mut options = FieldOptions {
+        rename: serde_attribute(&field.attrs, "rename")?.map(|rename| rename.value()),
+        ..Default::default()
+    };
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("rexie"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") {
+                options.key = true;
+            } else if meta.path.is_ident("auto_increment") {
+                options.auto_increment = Some(meta.path.span());
+            } else if meta.path.is_ident("index") {
+                options.index = Some(if meta.input.peek(syn::Token![=]) {
+                    Some(meta.value()?.parse::<LitStr>()?.value())
+                } else {
+                    None
+                });
+            } else if meta.path.is_ident("unique") {
+                options.unique = Some(meta.path.span());
+            } else if meta.path.is_ident("multi_entry") {
+                options.multi_entry = Some(meta.path.span());
+            } else if meta.path.is_ident("version") {
+                options.version = Some(meta.path.span());
+            } else {
+                return Err(meta.error(
+                    "unknown `rexie` attribute on a field, expected one of `key`, `auto_increment`, `index`, \
+                     `unique`, `multi_entry` or `version`",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
+
+/// Reads the (serialization) value of `#[serde(<name> = "..")]` or `#[serde(<name>(serialize = ".."))]`
+fn serde_attribute(attrs: &[Attribute], name: &str) -> Result<Option<LitStr>> {
+    let mut value = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(name) {
+                return skip_meta(&meta);
+            }
+
+            if meta.input.peek(Token![=]) {
+                value = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("serialize") {
+                        value = Some(meta.value()?.parse::<LitStr>()?);
+                        Ok(())
+                    } else {
+                        skip_meta(&meta)
+                    }
+                })
+            }
+        })?;
+    }
+
+    Ok(value)
+}
+
+/// Skips the value (`= ..` or `(..)`) of a nested meta which is not used
+fn skip_meta(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::TokenTree>()?;
+    }
+
+    Ok(())
+}
+
+/// Renames a (snake case) field the same way as `#[serde(rename_all = "..")]`
+fn rename_field(field_name: &str, rule: &LitStr) -> Result<String> {
+    let renamed = match rule.value().as_str() {
+        "lowercase" => field_name.to_ascii_lowercase(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field_name.to_ascii_uppercase(),
+        "snake_case" => field_name.to_owned(),
+        "kebab-case" => field_name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field_name.to_ascii_uppercase().replace('_', "-"),
+        "PascalCase" => to_pascal_case(field_name),
+        "camelCase" => {
+            let pascal_case = to_pascal_case(field_name);
+            let mut chars = pascal_case.chars();
+
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        _ => return Err(Error::new(rule.span(), "unknown serde `rename_all` rule")),
+    };
+
+    Ok(renamed)
+}
+
+fn to_pascal_case(name: &str) -> String {
+    let mut pascal_case = String::with_capacity(name.len());
+    let mut capitalize = true;
+
+    for c in name.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            pascal_case.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            pascal_case.push(c);
+        }
+    }
+
+    pascal_case
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake_case = String::with_capacity(name.len());
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake_case.push('_');
+            }
+
+            snake_case.extend(c.to_lowercase());
+        } else {
+            snake_case.push(c);
+        }
+    }
+
+    snake_case
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand_err(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_invalid_attributes() {
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book(String);
+            }),
+            "`Record` can only be derived for structs with named fields"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                enum Book { Paperback }
+            }),
+            "`Record` can only be derived for structs"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[rexie(table = "books")]
+                struct Book { isbn: String }
+            }),
+            "unknown `rexie` attribute on a struct, expected `store`"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book { #[rexie(primary)] isbn: String }
+            }),
+            "unknown `rexie` attribute on a field, expected one of `key`, `auto_increment`, `index`, `unique`, \
+             `multi_entry` or `version`"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book { #[rexie(auto_increment)] id: u32 }
+            }),
+            "`auto_increment` can only be used on a key field"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book { #[rexie(unique)] isbn: String }
+            }),
+            "`unique` and `multi_entry` can only be used on an index field"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book {
+                    #[rexie(key, auto_increment)]
+                    id: u32,
+                    #[rexie(key)]
+                    isbn: String,
+                }
+            }),
+            "`auto_increment` can't be used with a compound key"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct Book {
+                    #[rexie(version)]
+                    version: u32,
+                    #[rexie(version)]
+                    revision: u32,
+                }
+            }),
+            "`version` can only be used on a single field"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[serde(rename_all = "Title Case")]
+                struct Book { isbn: String }
+            }),
+            "unknown serde `rename_all` rule"
+        );
+    }
+
+    #[test]
+    fn follows_serde_names() {
+        let tokens = expand(parse_quote! {
+            #[derive(Serialize)]
+            #[serde(rename_all = "camelCase", deny_unknown_fields)]
+            struct Book {
+                #[rexie(key)]
+                book_id: String,
+                #[rexie(index)]
+                #[serde(rename(serialize = "writer", deserialize = "author"), default)]
+                author_name: String,
+                #[rexie(index, multi_entry)]
+                #[serde(skip_serializing_if = "Vec::is_empty")]
+                subject_tags: Vec<String>,
+            }
+        })
+        .unwrap()
+        .to_string();
+
+        assert!(tokens.contains("key_path (\"bookId\")"));
+        assert!(tokens.contains("Index :: new (\"author_name\" , \"writer\")"));
+        assert!(tokens.contains("Index :: new (\"subject_tags\" , \"subjectTags\")"));
+    }
+}
@@ -1,4 +1,7 @@
 use thiserror::Error;
+use wasm_bindgen::JsValue;
+
+use crate::Key;
 
 /// Result with `rexie::Error` as error type.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -16,4 +19,69 @@ pub enum Error {
     /// Couldn't commit a transaction
     #[error("couldn't commit a transaction")]
     TransactioncommitFailed,
+    /// Declared schema differs from the one in the browser but the version of the database wasn't bumped
+    #[error("schema has changed but version {0} is not greater than the current version {1}")]
+    VersionNotBumped(u32, u32),
+    /// Opening the database is blocked by other connections which didn't close after a version change
+    #[error("opening the database is blocked by other connections")]
+    Blocked,
+    /// Connection was closed because another connection wanted to upgrade or delete the database
+    #[error("connection was closed because of a version change")]
+    VersionChanged,
+    /// Couldn't list the databases (`IDBFactory.databases()` failed or isn't supported by the browser)
+    #[error("couldn't list the databases")]
+    DatabaseListFailed(JsValue),
+    /// Database with given name doesn't exist
+    #[error("database `{0}` doesn't exist")]
+    DatabaseNotFound(String),
+    /// Value isn't a valid key (keys can be numbers, strings, dates, binary values or arrays of keys)
+    #[error("value isn't a valid key")]
+    InvalidKey(JsValue),
+    /// Value returned by the update closure of [`Store::update`](crate::Store::update) doesn't have the key of the
+    /// updated record
+    #[error("updated value doesn't have the key {0:?}")]
+    KeyChanged(Key),
+    /// Integer can't be used as a key because it is outside `±Number.MAX_SAFE_INTEGER`, i.e., it can't be represented
+    /// exactly by a JavaScript number
+    #[error("integer {0} can't be represented exactly as a key")]
+    UnsafeInteger(String),
+    /// Key can't be converted to the requested type
+    #[error("key {0:?} can't be converted to the requested type")]
+    KeyTypeMismatch(Key),
+    /// Version of the stored value differs from the expected one (see
+    /// [`Store::put_if_version`](crate::Store::put_if_version))
+    #[error("version conflict: expected version {expected} but found {found}")]
+    VersionConflict {
+        /// Version expected by the caller
+        expected: u32,
+        /// Version of the stored value
+        found: u32,
+    },
+    /// Version can't be bumped because it is already the maximum version
+    #[error("version {0} can't be bumped")]
+    VersionOverflow(u32),
+    /// Value is not an object (see [`Store::put_if_version`](crate::Store::put_if_version))
+    #[error("value is not an object")]
+    NotAnObject(JsValue),
+    /// Store has no version attribute configured (see [`ObjectStore::version_attribute`](crate::ObjectStore::version_attribute))
+    #[error("store `{0}` has no version attribute")]
+    VersionAttributeMissing(String),
+    /// Bulk import failed. `committed` records of the input were committed before the failure, so the import can be
+    /// resumed from there using [`ImportOptions::resume_from`](crate::ImportOptions::resume_from).
+    #[error("bulk import failed after {committed} records were committed")]
+    ImportFailed {
+        /// Number of records of the input committed before the failure
+        committed: usize,
+        /// Error which caused the failure
+        #[source]
+        source: Box<Error>,
+    },
+    /// Couldn't serialize a value before writing it to a typed store
+    #[cfg(feature = "serde")]
+    #[error("couldn't serialize a value: {0}")]
+    SerializationFailed(String),
+    /// Couldn't deserialize a value read from a typed store, i.e., the stored value doesn't match the type of the store
+    #[cfg(feature = "serde")]
+    #[error("couldn't deserialize a value: {0}")]
+    DeserializationFailed(String),
 }
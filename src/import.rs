@@ -0,0 +1,54 @@
+/// Options of [`Rexie::bulk_import`](crate::Rexie::bulk_import).
+pub struct ImportOptions {
+    pub(crate) chunk_size: usize,
+    pub(crate) resume_from: usize,
+    pub(crate) on_progress: Option<Box<dyn FnMut(ImportProgress)>>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1000,
+            resume_from: 0,
+            on_progress: None,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Creates new import options (chunks of `1000` records by default)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the number of records written in each transaction
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Specify the number of records which were already committed by a previous import of the same input (see
+    /// [`Error::ImportFailed`](crate::Error::ImportFailed)). These records are skipped.
+    pub fn resume_from(mut self, committed: usize) -> Self {
+        self.resume_from = committed;
+        self
+    }
+
+    /// Specify a callback which is invoked after each chunk is committed
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(ImportProgress) + 'static,
+    {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+}
+
+/// Progress of a bulk import (passed to [`ImportOptions::on_progress`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// Number of chunks committed by this import
+    pub chunks: usize,
+    /// Number of records of the input committed so far (including the records skipped when resuming)
+    pub committed: usize,
+}
@@ -1,36 +1,67 @@
-use idb::builder::IndexBuilder;
+use idb::{IndexParams, ObjectStore as IdbObjectStore};
 
-use crate::KeyPath;
+use crate::{KeyPath, Result};
 
 /// An index builder.
 pub struct Index {
-    pub(crate) builder: IndexBuilder,
+    pub(crate) name: String,
+    pub(crate) key_path: KeyPath,
+    pub(crate) unique: bool,
+    pub(crate) multi_entry: bool,
 }
 
 impl Index {
     /// Creates a new index with given name and key path
     pub fn new(name: &str, key_path: &str) -> Self {
         Self {
-            builder: IndexBuilder::new(name.to_owned(), KeyPath::new_single(key_path)),
+            name: name.to_owned(),
+            key_path: KeyPath::new_single(key_path),
+            unique: false,
+            multi_entry: false,
         }
     }
 
     /// Creates a new index with given name and key path array
     pub fn new_array<'a>(name: &str, key_path_array: impl IntoIterator<Item = &'a str>) -> Self {
         Self {
-            builder: IndexBuilder::new(name.to_owned(), KeyPath::new_array(key_path_array)),
+            name: name.to_owned(),
+            key_path: KeyPath::new_array(key_path_array),
+            unique: false,
+            multi_entry: false,
         }
     }
 
     /// Specify whether the index should be unique
     pub fn unique(mut self, unique: bool) -> Self {
-        self.builder = self.builder.unique(unique);
+        self.unique = unique;
         self
     }
 
     /// Specify whether the index should be multi-entry, i.e., type of the value contained in key path is an array
     pub fn multi_entry(mut self, multi_entry: bool) -> Self {
-        self.builder = self.builder.multi_entry(multi_entry);
+        self.multi_entry = multi_entry;
         self
     }
+
+    /// Creates the index on given object store. An existing index with the same name is re-created only if its key
+    /// path or flags differ.
+    pub(crate) fn apply(self, object_store: &IdbObjectStore) -> Result<()> {
+        if let Ok(existing_index) = object_store.index(&self.name) {
+            if existing_index.key_path()?.as_ref() == Some(&self.key_path)
+                && existing_index.unique() == self.unique
+                && existing_index.multi_entry() == self.multi_entry
+            {
+                return Ok(());
+            }
+
+            object_store.delete_index(&self.name)?;
+        }
+
+        let mut params = IndexParams::new();
+        params.unique(self.unique).multi_entry(self.multi_entry);
+
+        object_store.create_index(&self.name, self.key_path, Some(params))?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,311 @@
+use std::cmp::Ordering;
+
+use js_sys::{Array, ArrayBuffer, Date, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::Error;
+
+/// A key of a record in an object store or an index.
+///
+/// [Reference](https://developer.mozilla.org/en-US/docs/Web/API/IndexedDB_API/Basic_Terminology#key)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    /// A number (except `NaN`)
+    Number(f64),
+    /// A string
+    String(String),
+    /// A date (milliseconds since the unix epoch)
+    Date(f64),
+    /// A binary value (stored as an `ArrayBuffer`)
+    Binary(Vec<u8>),
+    /// An array of keys, i.e., a compound key
+    Array(Vec<Key>),
+}
+
+impl Key {
+    /// Rank of the type of the key in the ordering of keys
+    fn type_rank(&self) -> u8 {
+        match self {
+            Key::Number(_) => 0,
+            Key::Date(_) => 1,
+            Key::String(_) => 2,
+            Key::Binary(_) => 3,
+            Key::Array(_) => 4,
+        }
+    }
+}
+
+/// Keys are ordered the same way as in IndexedDB, i.e., numbers < dates < strings < binary values < arrays. Strings
+/// are compared by their UTF-16 code units and arrays are compared element by element.
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Key::Number(a), Key::Number(b)) | (Key::Date(a), Key::Date(b)) => a.partial_cmp(b),
+            (Key::String(a), Key::String(b)) => Some(a.encode_utf16().cmp(b.encode_utf16())),
+            (Key::Binary(a), Key::Binary(b)) => Some(a.cmp(b)),
+            (Key::Array(a), Key::Array(b)) => a.partial_cmp(b),
+            (a, b) => Some(a.type_rank().cmp(&b.type_rank())),
+        }
+    }
+}
+
+/// Largest integer which can be represented exactly by a JavaScript number (`Number.MAX_SAFE_INTEGER`)
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+macro_rules! impl_number_key {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Key {
+                fn from(value: $ty) -> Self {
+                    Key::Number(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_number_key!(u8, u16, u32, i8, i16, i32);
+
+/// Integers which don't always fit in a JavaScript number are converted only if they are within
+/// `±Number.MAX_SAFE_INTEGER`, so that different integers never map to the same key.
+macro_rules! impl_large_number_key {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<$ty> for Key {
+                type Error = Error;
+
+                fn try_from(value: $ty) -> Result<Self, Self::Error> {
+                    let number = value as f64;
+
+                    if number.abs() <= MAX_SAFE_INTEGER {
+                        Ok(Key::Number(number))
+                    } else {
+                        Err(Error::UnsafeInteger(value.to_string()))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_large_number_key!(u64, usize, i64, isize);
+
+macro_rules! impl_number_try_from_key {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Key> for $ty {
+                type Error = Error;
+
+                fn try_from(key: Key) -> Result<Self, Self::Error> {
+                    let min = (<$ty>::MIN as f64).max(-MAX_SAFE_INTEGER);
+                    let max = (<$ty>::MAX as f64).min(MAX_SAFE_INTEGER);
+
+                    match key {
+                        Key::Number(value) if value.fract() == 0.0 && value >= min && value <= max => {
+                            Ok(value as $ty)
+                        }
+                        key => Err(Error::KeyTypeMismatch(key)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_number_try_from_key!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl From<f32> for Key {
+    fn from(value: f32) -> Self {
+        Key::Number(value.into())
+    }
+}
+
+impl From<f64> for Key {
+    fn from(value: f64) -> Self {
+        Key::Number(value)
+    }
+}
+
+impl TryFrom<Key> for f64 {
+    type Error = Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::Number(value) => Ok(value),
+            key => Err(Error::KeyTypeMismatch(key)),
+        }
+    }
+}
+
+impl From<&str> for Key {
+    fn from(value: &str) -> Self {
+        Key::String(value.to_owned())
+    }
+}
+
+impl From<&String> for Key {
+    fn from(value: &String) -> Self {
+        Key::String(value.clone())
+    }
+}
+
+impl From<String> for Key {
+    fn from(value: String) -> Self {
+        Key::String(value)
+    }
+}
+
+impl TryFrom<Key> for String {
+    type Error = Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::String(value) => Ok(value),
+            key => Err(Error::KeyTypeMismatch(key)),
+        }
+    }
+}
+
+impl From<Date> for Key {
+    fn from(value: Date) -> Self {
+        Key::Date(value.get_time())
+    }
+}
+
+impl TryFrom<Key> for Date {
+    type Error = Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::Date(value) => Ok(Date::new(&value.into())),
+            key => Err(Error::KeyTypeMismatch(key)),
+        }
+    }
+}
+
+impl From<&[u8]> for Key {
+    fn from(value: &[u8]) -> Self {
+        Key::Binary(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Key {
+    fn from(value: Vec<u8>) -> Self {
+        Key::Binary(value)
+    }
+}
+
+impl TryFrom<Key> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::Binary(value) => Ok(value),
+            key => Err(Error::KeyTypeMismatch(key)),
+        }
+    }
+}
+
+impl From<Vec<Key>> for Key {
+    fn from(value: Vec<Key>) -> Self {
+        Key::Array(value)
+    }
+}
+
+impl TryFrom<Key> for Vec<Key> {
+    type Error = Error;
+
+    fn try_from(key: Key) -> Result<Self, Self::Error> {
+        match key {
+            Key::Array(value) => Ok(value),
+            key => Err(Error::KeyTypeMismatch(key)),
+        }
+    }
+}
+
+macro_rules! impl_tuple_key {
+    ($(($($name:ident),+)),*) => {
+        $(
+            impl<$($name: Into<Key>),+> From<($($name,)+)> for Key {
+                #[allow(non_snake_case)]
+                fn from(($($name,)+): ($($name,)+)) -> Self {
+                    Key::Array(vec![$($name.into()),+])
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple_key!((A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
+impl From<&Key> for JsValue {
+    fn from(key: &Key) -> Self {
+        match key {
+            Key::Number(value) => JsValue::from_f64(*value),
+            Key::String(value) => JsValue::from_str(value),
+            Key::Date(value) => Date::new(&JsValue::from_f64(*value)).into(),
+            Key::Binary(value) => Uint8Array::from(value.as_slice()).buffer().into(),
+            Key::Array(value) => value.iter().map(JsValue::from).collect::<Array>().into(),
+        }
+    }
+}
+
+impl From<Key> for JsValue {
+    fn from(key: Key) -> Self {
+        (&key).into()
+    }
+}
+
+impl TryFrom<&JsValue> for Key {
+    type Error = Error;
+
+    fn try_from(value: &JsValue) -> Result<Self, Self::Error> {
+        if let Some(number) = value.as_f64() {
+            if !number.is_nan() {
+                return Ok(Key::Number(number));
+            }
+        } else if let Some(string) = value.as_string() {
+            return Ok(Key::String(string));
+        } else if let Some(date) = value.dyn_ref::<Date>() {
+            let time = date.get_time();
+
+            if !time.is_nan() {
+                return Ok(Key::Date(time));
+            }
+        } else if value.is_instance_of::<ArrayBuffer>() {
+            return Ok(Key::Binary(Uint8Array::new(value).to_vec()));
+        } else if ArrayBuffer::is_view(value) {
+            return Ok(Key::Binary(view_bytes(value)?));
+        } else if let Some(array) = value.dyn_ref::<Array>() {
+            return array
+                .iter()
+                .map(|value| Key::try_from(&value))
+                .collect::<Result<_, _>>()
+                .map(Key::Array);
+        }
+
+        Err(Error::InvalidKey(value.clone()))
+    }
+}
+
+impl TryFrom<JsValue> for Key {
+    type Error = Error;
+
+    fn try_from(value: JsValue) -> Result<Self, Self::Error> {
+        Key::try_from(&value)
+    }
+}
+
+/// Copies the bytes viewed by a typed array or a `DataView`
+fn view_bytes(view: &JsValue) -> Result<Vec<u8>, Error> {
+    let get = |property: &str| {
+        Reflect::get(view, &JsValue::from_str(property))
+            .map_err(|_| Error::InvalidKey(view.clone()))
+    };
+
+    let buffer = get("buffer")?;
+    let byte_offset = get("byteOffset")?.as_f64().unwrap_or_default() as u32;
+    let byte_length = get("byteLength")?.as_f64().unwrap_or_default() as u32;
+
+    Ok(Uint8Array::new_with_byte_offset_and_length(&buffer, byte_offset, byte_length).to_vec())
+}
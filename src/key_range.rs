@@ -1,7 +1,9 @@
+use std::ops::{Bound, RangeBounds};
+
 use idb::{KeyRange as IdbKeyRange, Query};
 use wasm_bindgen::JsValue;
 
-use crate::Error;
+use crate::{Error, Key};
 
 /// Represents a continuous interval over some data type that is used for keys.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,47 +13,109 @@ pub struct KeyRange {
 
 impl KeyRange {
     /// Returns a new [`KeyRange`] spanning only key.
-    pub fn only(value: &JsValue) -> Result<Self, Error> {
-        IdbKeyRange::only(value).map(Into::into).map_err(Into::into)
+    pub fn only(value: impl Into<Key>) -> Result<Self, Error> {
+        IdbKeyRange::only(&value.into().into())
+            .map(Into::into)
+            .map_err(Into::into)
     }
 
     /// Returns a new [`KeyRange`] spanning from lower to upper. If `lower_open` is true, `lower` is not included in the
     /// range. If `upper_open` is true, `upper` is not included in the range.
     pub fn bound(
-        lower: &JsValue,
-        upper: &JsValue,
+        lower: impl Into<Key>,
+        upper: impl Into<Key>,
         lower_open: Option<bool>,
         upper_open: Option<bool>,
     ) -> Result<Self, Error> {
-        IdbKeyRange::bound(lower, upper, lower_open, upper_open)
-            .map(Into::into)
-            .map_err(Into::into)
+        IdbKeyRange::bound(
+            &lower.into().into(),
+            &upper.into().into(),
+            lower_open,
+            upper_open,
+        )
+        .map(Into::into)
+        .map_err(Into::into)
     }
 
     /// Returns a new [`KeyRange`] starting at key with no upper bound. If `lower_open` is true, key is not included in
     /// the range.
-    pub fn lower_bound(lower: &JsValue, lower_open: Option<bool>) -> Result<Self, Error> {
-        IdbKeyRange::lower_bound(lower, lower_open)
+    pub fn lower_bound(lower: impl Into<Key>, lower_open: Option<bool>) -> Result<Self, Error> {
+        IdbKeyRange::lower_bound(&lower.into().into(), lower_open)
             .map(Into::into)
             .map_err(Into::into)
     }
 
     /// Returns a new [`KeyRange`] with no lower bound and ending at key. If `upper_open` is true, key is not included
     /// in the range.
-    pub fn upper_bound(upper: &JsValue, upper_open: Option<bool>) -> Result<Self, Error> {
-        IdbKeyRange::upper_bound(upper, upper_open)
+    pub fn upper_bound(upper: impl Into<Key>, upper_open: Option<bool>) -> Result<Self, Error> {
+        IdbKeyRange::upper_bound(&upper.into().into(), upper_open)
             .map(Into::into)
             .map_err(Into::into)
     }
 
-    /// Returns the range’s lower bound, or undefined if none.
-    pub fn lower(&self) -> Result<JsValue, Error> {
-        self.inner.lower().map_err(Into::into)
+    /// Returns a new [`KeyRange`] from Rust range syntax, e.g., `10..=20`, `..5` or `"a".."b"`. Tuples can be used as
+    /// bounds of compound keys, e.g., `(1, "a")..(2, "a")`.
+    ///
+    /// A range with equal inclusive bounds spans only the key and an unbounded range (`..`) spans all the keys.
+    pub fn from_range<K>(range: impl RangeBounds<K>) -> Result<Self, Error>
+    where
+        K: Clone + Into<Key>,
+    {
+        // Converts a bound to a key along with its open flag
+        let key_bound = |bound: Bound<&K>| match bound {
+            Bound::Included(key) => Some((key.clone().into(), false)),
+            Bound::Excluded(key) => Some((key.clone().into(), true)),
+            Bound::Unbounded => None,
+        };
+
+        match (key_bound(range.start_bound()), key_bound(range.end_bound())) {
+            (Some((lower, false)), Some((upper, false))) if lower == upper => Self::only(lower),
+            (Some((lower, lower_open)), Some((upper, upper_open))) => {
+                Self::bound(lower, upper, Some(lower_open), Some(upper_open))
+            }
+            (Some((lower, lower_open)), None) => Self::lower_bound(lower, Some(lower_open)),
+            (None, Some((upper, upper_open))) => Self::upper_bound(upper, Some(upper_open)),
+            // `-Infinity` is the smallest possible key
+            (None, None) => Self::lower_bound(f64::NEG_INFINITY, None),
+        }
+    }
+
+    /// Returns a new [`KeyRange`] spanning all the string keys starting with `prefix`.
+    ///
+    /// The upper bound is `prefix` followed by the `\u{ffff}` sentinel, so it doesn't span keys where the prefix is
+    /// followed by `\u{ffff}` itself.
+    pub fn starts_with(prefix: &str) -> Result<Self, Error> {
+        Self::bound(
+            prefix,
+            format!("{prefix}\u{ffff}"),
+            Some(false),
+            Some(false),
+        )
+    }
+
+    /// Returns a new [`KeyRange`] spanning all the compound (array) keys whose leading elements are equal to `prefix`,
+    /// e.g., `KeyRange::prefix(["John Doe"])` spans `["John Doe", "Umbrella Corp"]` in an index with key path
+    /// `["agent", "customer"]`.
+    ///
+    /// The upper bound is `prefix` followed by an empty array (the sentinel for compound keys), so it doesn't span
+    /// keys where the prefix is followed by an array.
+    pub fn prefix<K: Into<Key>>(prefix: impl IntoIterator<Item = K>) -> Result<Self, Error> {
+        let lower: Vec<Key> = prefix.into_iter().map(Into::into).collect();
+
+        let mut upper = lower.clone();
+        upper.push(Key::Array(Vec::new()));
+
+        Self::bound(lower, upper, Some(false), Some(true))
+    }
+
+    /// Returns the range’s lower bound, or `None` if none.
+    pub fn lower(&self) -> Result<Option<Key>, Error> {
+        optional_key(self.inner.lower()?)
     }
 
-    /// Returns the range’s upper bound, or undefined if none.
-    pub fn upper(&self) -> Result<JsValue, Error> {
-        self.inner.upper().map_err(Into::into)
+    /// Returns the range’s upper bound, or `None` if none.
+    pub fn upper(&self) -> Result<Option<Key>, Error> {
+        optional_key(self.inner.upper()?)
     }
 
     /// Returns the range’s lower open flag.
@@ -65,8 +129,18 @@ impl KeyRange {
     }
 
     /// Returns true if key is included in the range, and false otherwise.
-    pub fn includes(&self, value: &JsValue) -> Result<bool, Error> {
-        self.inner.includes(value).map_err(Into::into)
+    pub fn includes(&self, value: impl Into<Key>) -> Result<bool, Error> {
+        self.inner
+            .includes(&value.into().into())
+            .map_err(Into::into)
+    }
+}
+
+fn optional_key(value: JsValue) -> Result<Option<Key>, Error> {
+    if value.is_undefined() {
+        Ok(None)
+    } else {
+        Key::try_from(value).map(Some)
     }
 }
 
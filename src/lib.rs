@@ -71,7 +71,7 @@
 //!     transaction.done().await?;
 //!
 //!     // Return the employee id
-//!     Ok(num_traits::cast(employee_id.as_f64().unwrap()).unwrap())
+//!     employee_id.try_into()
 //! }
 //! ```
 //!
@@ -88,7 +88,7 @@
 //!     let employees = transaction.store("employees")?;
 //!     
 //!     // Get the employee
-//!     let employee = employees.get(id.into()).await?.unwrap();
+//!     let employee = employees.get(id).await?.unwrap();
 //!
 //!     // Convert it to `serde_json::Value` from `JsValue`
 //!     let employee: Option<serde_json::Value> = serde_wasm_bindgen::from_value(employee).unwrap();
@@ -98,21 +98,36 @@
 //! }
 //! ```
 mod error;
+mod import;
 mod index;
+mod key;
 mod key_range;
 mod object_store;
+mod record;
 mod rexie;
 mod rexie_builder;
+mod schema;
 mod transaction;
+mod upgrade;
 
 pub use idb::{CursorDirection as Direction, KeyPath, TransactionMode, TransactionResult};
 
 pub use self::{
     error::{Error, Result},
+    import::{ImportOptions, ImportProgress},
     index::Index,
+    key::Key,
     key_range::KeyRange,
     object_store::ObjectStore,
-    rexie::Rexie,
+    record::Record,
+    rexie::{DatabaseInfo, Rexie},
     rexie_builder::RexieBuilder,
-    transaction::{Store, StoreIndex, Transaction},
+    schema::{IndexSchema, Schema, SchemaDiff, StoreSchema},
+    transaction::{BatchMode, Page, PageToken, Store, StoreIndex, Transaction},
+    upgrade::Upgrade,
 };
+
+#[cfg(feature = "serde")]
+pub use self::transaction::TypedStore;
+#[cfg(feature = "derive")]
+pub use rexie_derive::Record;
@@ -1,43 +1,105 @@
-use idb::builder::ObjectStoreBuilder;
+use idb::{Database, ObjectStoreParams, Transaction as IdbTransaction};
 
-use crate::{Index, KeyPath};
+use crate::{Index, KeyPath, Result};
 
 /// An object store builder.
 pub struct ObjectStore {
-    pub(crate) builder: ObjectStoreBuilder,
+    pub(crate) name: String,
+    pub(crate) key_path: Option<KeyPath>,
+    pub(crate) auto_increment: bool,
+    pub(crate) indexes: Vec<Index>,
+    pub(crate) indexes_to_delete: Vec<String>,
+    pub(crate) version_attribute: Option<String>,
 }
 
 impl ObjectStore {
     /// Creates a new object store with given name
     pub fn new(name: &str) -> Self {
         Self {
-            builder: ObjectStoreBuilder::new(name),
+            name: name.to_owned(),
+            key_path: None,
+            auto_increment: false,
+            indexes: Vec::new(),
+            indexes_to_delete: Vec::new(),
+            version_attribute: None,
         }
     }
 
     /// Specify key path for the object store
     pub fn key_path(mut self, key_path: &str) -> Self {
-        self.builder = self.builder.key_path(Some(KeyPath::new_single(key_path)));
+        self.key_path = Some(KeyPath::new_single(key_path));
         self
     }
 
     /// Specify key path array for the object store
     pub fn key_path_array<'a>(mut self, key_path_array: impl IntoIterator<Item = &'a str>) -> Self {
-        self.builder = self
-            .builder
-            .key_path(Some(KeyPath::new_array(key_path_array)));
+        self.key_path = Some(KeyPath::new_array(key_path_array));
         self
     }
 
     /// Specify whether the object store should auto increment keys
     pub fn auto_increment(mut self, auto_increment: bool) -> Self {
-        self.builder = self.builder.auto_increment(auto_increment);
+        self.auto_increment = auto_increment;
         self
     }
 
     /// Add an index to the object store
     pub fn add_index(mut self, index: Index) -> Self {
-        self.builder = self.builder.add_index(index.builder);
+        self.indexes.push(index);
         self
     }
+
+    /// Delete an index from the object store when the database is upgraded. If an index with the same name is also
+    /// added, it is re-created.
+    pub fn delete_index(mut self, name: &str) -> Self {
+        self.indexes_to_delete.push(name.to_owned());
+        self
+    }
+
+    /// Specify the attribute of the values which holds their version, enabling optimistic concurrency with
+    /// [`Store::put_if_version`](crate::Store::put_if_version). The attribute is not part of the schema in the browser,
+    /// i.e., it doesn't require an upgrade.
+    pub fn version_attribute(mut self, attribute: &str) -> Self {
+        self.version_attribute = Some(attribute.to_owned());
+        self
+    }
+
+    /// Creates the object store (if it doesn't exist already) along with its indexes in an upgrade transaction. If
+    /// `delete_undeclared_indexes` is `true`, indexes which exist in the database but are not declared on the object
+    /// store are deleted.
+    pub(crate) fn apply(
+        self,
+        database: &Database,
+        transaction: &IdbTransaction,
+        delete_undeclared_indexes: bool,
+    ) -> Result<()> {
+        let object_store = if database.store_names().contains(&self.name) {
+            transaction.object_store(&self.name)?
+        } else {
+            let mut params = ObjectStoreParams::new();
+            params
+                .auto_increment(self.auto_increment)
+                .key_path(self.key_path);
+
+            database.create_object_store(&self.name, params)?
+        };
+
+        let index_names = object_store.index_names();
+
+        for index_name in index_names {
+            let declared = self.indexes.iter().any(|index| index.name == index_name);
+
+            if self.indexes_to_delete.contains(&index_name)
+                || (delete_undeclared_indexes && !declared)
+            {
+                object_store.delete_index(&index_name)?;
+            }
+        }
+
+        for index in self.indexes {
+            index.apply(&object_store)?;
+        }
+
+        Ok(())
+    }
 }
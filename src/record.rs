@@ -0,0 +1,28 @@
+use crate::ObjectStore;
+
+/// A record type which is stored in its own object store.
+///
+/// Instead of implementing this trait by hand, it can be derived (with the `derive` feature) by marking the key and
+/// indexed fields of a struct:
+///
+/// ```ignore
+/// #[derive(rexie::Record)]
+/// #[rexie(store = "employees")]
+/// struct Employee {
+///     #[rexie(key, auto_increment)]
+///     id: u32,
+///     name: String,
+///     #[rexie(index, unique)]
+///     email: String,
+/// }
+/// ```
+///
+/// The object store is then declared with `Employee::object_store()` and, with the `serde` feature, accessed with
+/// [`Transaction::record_store`](crate::Transaction::record_store).
+pub trait Record {
+    /// Name of the object store of the record
+    const STORE_NAME: &'static str;
+
+    /// Returns the object store (along with its indexes) of the record
+    fn object_store() -> ObjectStore;
+}
@@ -1,19 +1,60 @@
+use std::{cell::Cell, collections::HashMap, future::Future, rc::Rc};
+
+use futures_core::Stream;
+use futures_util::StreamExt;
 use idb::Database;
+use js_sys::{Array, Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::IdbTransaction;
+
+use crate::{
+    Error, ImportOptions, ImportProgress, Key, Result, RexieBuilder, Schema, StoreSchema,
+    Transaction, TransactionMode,
+};
 
-use crate::{Result, RexieBuilder, Transaction, TransactionMode};
+/// Name and version of an existing database (returned by [`Rexie::databases`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseInfo {
+    /// Name of the database
+    pub name: String,
+    /// Version of the database
+    pub version: u32,
+}
 
 /// Rexie database (wrapper on top of indexed db)
 #[derive(Debug)]
 pub struct Rexie {
     pub(crate) database: Database,
+    pub(crate) version_changed: Rc<Cell<bool>>,
+    pub(crate) version_attributes: Rc<HashMap<String, String>>,
 }
 
 impl Rexie {
+    pub(crate) fn new(database: Database) -> Self {
+        Self {
+            database,
+            version_changed: Default::default(),
+            version_attributes: Default::default(),
+        }
+    }
+
     /// Creates a builder for database with given name
     pub fn builder(name: &str) -> RexieBuilder {
         RexieBuilder::new(name)
     }
 
+    /// Opens an existing database at its current version without upgrading it. Fails with
+    /// [`Error::DatabaseNotFound`] if the database doesn't exist.
+    ///
+    /// Note that the stores of the returned connection have no version attributes (see
+    /// [`ObjectStore::version_attribute`](crate::ObjectStore::version_attribute)), so
+    /// [`Store::put_if_version`](crate::Store::put_if_version) fails. Use [`RexieBuilder::open_existing`] with the
+    /// object stores declared to keep them.
+    pub async fn open_existing(name: &str) -> Result<Rexie> {
+        Self::builder(name).open_existing().await
+    }
+
     /// Returns name of the database
     pub fn name(&self) -> String {
         self.database.name()
@@ -29,14 +70,200 @@ impl Rexie {
         self.database.store_names()
     }
 
+    /// Returns a snapshot of the schema of the database, i.e., all the stores along with their indexes
+    pub fn schema(&self) -> Result<Schema> {
+        let store_names = self.store_names();
+        let mut stores = Vec::with_capacity(store_names.len());
+
+        if !store_names.is_empty() {
+            let transaction = self.transaction(&store_names, TransactionMode::ReadOnly)?;
+
+            for store_name in store_names.iter() {
+                stores.push(StoreSchema::new(
+                    &transaction.transaction.object_store(store_name)?,
+                )?);
+            }
+        }
+
+        Ok(Schema {
+            name: self.name(),
+            version: self.version()?,
+            stores,
+        })
+    }
+
+    /// Returns `true` if the connection was closed because another connection wanted to upgrade or delete the database
+    /// (see [`RexieBuilder::on_version_change`]). Once this happens, creating a new transaction fails with
+    /// [`Error::VersionChanged`].
+    pub fn is_version_changed(&self) -> bool {
+        self.version_changed.get()
+    }
+
     /// Creates a new transaction on the database
     pub fn transaction<T: AsRef<str>>(
         &self,
         store_names: &[T],
         mode: TransactionMode,
     ) -> Result<Transaction> {
+        if self.is_version_changed() {
+            return Err(Error::VersionChanged);
+        }
+
         let transaction = self.database.transaction(store_names, mode)?;
-        Ok(Transaction { transaction })
+        Ok(Transaction {
+            transaction,
+            version_attributes: self.version_attributes.clone(),
+        })
+    }
+
+    /// Runs `f` in a new `ReadOnly` transaction on given stores and returns its value once the transaction completes.
+    /// See [`write`](Self::write) for details.
+    pub async fn read<S, F, Fut, T, E>(&self, store_names: &[S], f: F) -> std::result::Result<T, E>
+    where
+        S: AsRef<str>,
+        F: FnOnce(Transaction) -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+        E: From<Error>,
+    {
+        self.scoped(store_names, TransactionMode::ReadOnly, f).await
+    }
+
+    /// Runs `f` in a new `ReadWrite` transaction on given stores. If `f` returns `Ok`, waits for the transaction to
+    /// commit and returns the value (or [`Error::TransactioncommitFailed`] if the transaction was aborted). If `f`
+    /// returns `Err`, aborts the transaction, rolling back all its writes, and returns the error.
+    ///
+    /// # Note
+    ///
+    /// The transaction commits automatically as soon as there are no pending requests on it. So, only requests on the
+    /// transaction should be awaited in `f`, otherwise writes may be committed before `f` returns.
+    pub async fn write<S, F, Fut, T, E>(&self, store_names: &[S], f: F) -> std::result::Result<T, E>
+    where
+        S: AsRef<str>,
+        F: FnOnce(Transaction) -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+        E: From<Error>,
+    {
+        self.scoped(store_names, TransactionMode::ReadWrite, f)
+            .await
+    }
+
+    /// Runs `f` in a new transaction, committing it on `Ok` and aborting it on `Err`
+    async fn scoped<S, F, Fut, T, E>(
+        &self,
+        store_names: &[S],
+        mode: TransactionMode,
+        f: F,
+    ) -> std::result::Result<T, E>
+    where
+        S: AsRef<str>,
+        F: FnOnce(Transaction) -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+        E: From<Error>,
+    {
+        let transaction = self.transaction(store_names, mode)?;
+        let inner = IdbTransaction::from(transaction.transaction);
+
+        // Completion is observed through event listeners (registered before running `f`) instead of the `oncomplete`
+        // and `onabort` handlers, which are replaced if `f` awaits its own handle (e.g., using `commit` or `done`).
+        let completion = JsFuture::from(Promise::new(&mut |resolve, reject| {
+            let _ = inner.add_event_listener_with_callback("complete", &resolve);
+            let _ = inner.add_event_listener_with_callback("abort", &reject);
+        }));
+
+        let handle = Transaction {
+            transaction: inner.clone().into(),
+            version_attributes: transaction.version_attributes,
+        };
+
+        match f(handle).await {
+            Ok(value) => match completion.await {
+                Ok(_) => Ok(value),
+                Err(_) => Err(Error::TransactioncommitFailed.into()),
+            },
+            Err(err) => {
+                // Aborting fails if the transaction has already finished (e.g., it was aborted by a failed request)
+                let _ = inner.abort();
+                let _ = completion.await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Puts (adds or updates) all key value pairs (`(value, Option<key>)`) of `records` in the store with given name,
+    /// in chunks of [`ImportOptions::chunk_size`] pairs. Each chunk is written in its own transaction, so a large
+    /// import doesn't keep a single long-running transaction open. Returns the number of records of the input
+    /// committed (including the records skipped when resuming).
+    ///
+    /// If a chunk fails, [`Error::ImportFailed`] reports the number of committed records, which can be passed to
+    /// [`ImportOptions::resume_from`] to resume the import with the same input.
+    pub async fn bulk_import(
+        &self,
+        store_name: &str,
+        records: impl Stream<Item = (JsValue, Option<Key>)>,
+        mut options: ImportOptions,
+    ) -> Result<usize> {
+        let mut records = std::pin::pin!(records.skip(options.resume_from));
+        let mut committed = options.resume_from;
+        let mut chunks = 0;
+
+        loop {
+            // Chunk is read before creating the transaction so that the transaction doesn't commit while waiting for
+            // the input
+            let mut chunk = Vec::with_capacity(options.chunk_size);
+
+            while chunk.len() < options.chunk_size {
+                match records.next().await {
+                    Some(record) => chunk.push(record),
+                    None => break,
+                }
+            }
+
+            if chunk.is_empty() {
+                return Ok(committed);
+            }
+
+            let chunk_len = chunk.len();
+
+            self.import_chunk(store_name, chunk)
+                .await
+                .map_err(|err| Error::ImportFailed {
+                    committed,
+                    source: Box::new(err),
+                })?;
+
+            committed += chunk_len;
+            chunks += 1;
+
+            if let Some(on_progress) = options.on_progress.as_mut() {
+                on_progress(ImportProgress { chunks, committed });
+            }
+        }
+    }
+
+    /// Writes a chunk of a bulk import in its own transaction
+    async fn import_chunk(
+        &self,
+        store_name: &str,
+        chunk: Vec<(JsValue, Option<Key>)>,
+    ) -> Result<()> {
+        let transaction = self.transaction(&[store_name], TransactionMode::ReadWrite)?;
+        let result = match transaction.store(store_name) {
+            Ok(store) => store.put_all(chunk.into_iter()).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            // Rolls back the records of the chunk which were already written (if a write failed synchronously, the
+            // transaction would commit them otherwise)
+            let _ = transaction.abort().await;
+            return Err(err);
+        }
+
+        if transaction.done().await?.is_committed() {
+            Ok(())
+        } else {
+            Err(Error::TransactioncommitFailed)
+        }
     }
 
     /// Closes the database
@@ -44,6 +271,46 @@ impl Rexie {
         self.database.close();
     }
 
+    /// Returns name and version of all the databases of the current origin (uses `IDBFactory.databases()`)
+    pub async fn databases() -> Result<Vec<DatabaseInfo>> {
+        let factory = Reflect::get(&js_sys::global(), &JsValue::from("indexedDB"))
+            .map_err(idb::Error::IndexedDbNotFound)?;
+
+        let promise: Promise = Reflect::get(&factory, &JsValue::from("databases"))
+            .and_then(|databases| databases.dyn_into::<Function>())
+            .and_then(|databases| databases.call0(&factory))
+            .and_then(|promise| promise.dyn_into())
+            .map_err(Error::DatabaseListFailed)?;
+
+        let databases = JsFuture::from(promise)
+            .await
+            .map_err(Error::DatabaseListFailed)?;
+
+        Array::from(&databases)
+            .iter()
+            .map(|database| {
+                let name = Reflect::get(&database, &JsValue::from("name"))
+                    .map_err(Error::DatabaseListFailed)?
+                    .as_string()
+                    .unwrap_or_default();
+                let version = Reflect::get(&database, &JsValue::from("version"))
+                    .map_err(Error::DatabaseListFailed)?
+                    .as_f64()
+                    .ok_or(idb::Error::NumberConversionError)? as u32;
+
+                Ok(DatabaseInfo { name, version })
+            })
+            .collect()
+    }
+
+    /// Returns `true` if a database with given name exists
+    pub async fn exists(name: &str) -> Result<bool> {
+        Ok(Self::databases()
+            .await?
+            .iter()
+            .any(|database| database.name == name))
+    }
+
     /// Deletes a database. Note that the future returned by this function doesn't reach completion until the database
     /// is closed across all tabs in the browser.
     pub async fn delete(name: &str) -> Result<()> {
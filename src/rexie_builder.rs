@@ -1,11 +1,35 @@
-use idb::{builder::DatabaseBuilder, Factory};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    future::{poll_fn, Future, IntoFuture},
+    pin::Pin,
+    rc::Rc,
+    task::{Poll, Waker},
+};
 
-use crate::{ObjectStore, Result, Rexie};
+use idb::{event::VersionChangeEvent, Database, DatabaseEvent, Event, Factory, Request};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::{Error, ObjectStore, Result, Rexie, StoreSchema, Upgrade};
+
+type UpgradeCallback = Box<dyn FnOnce(Upgrade) -> Pin<Box<dyn Future<Output = Result<()>>>>>;
+
+type BlockedCallback = Box<dyn FnOnce(u32, u32)>;
+
+type VersionChangeCallback = Box<dyn FnOnce(u32, Option<u32>)>;
 
 /// Builder for creating a new database.
 pub struct RexieBuilder {
     name: String,
-    builder: DatabaseBuilder,
+    version: Option<u32>,
+    object_stores: Vec<ObjectStore>,
+    object_stores_to_delete: Vec<String>,
+    migrations: BTreeMap<u32, UpgradeCallback>,
+    upgrade_callback: Option<UpgradeCallback>,
+    auto_upgrade: bool,
+    blocked_callback: Option<BlockedCallback>,
+    version_change_callback: Option<VersionChangeCallback>,
 }
 
 impl RexieBuilder {
@@ -13,26 +37,282 @@ impl RexieBuilder {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_owned(),
-            builder: DatabaseBuilder::new(name),
+            version: None,
+            object_stores: Vec::new(),
+            object_stores_to_delete: Vec::new(),
+            migrations: BTreeMap::new(),
+            upgrade_callback: None,
+            auto_upgrade: false,
+            blocked_callback: None,
+            version_change_callback: None,
         }
     }
 
-    /// Specify version of the database.
+    /// Specify version of the database. If not specified, the highest version among the added migrations is used.
     pub fn version(mut self, version: u32) -> Self {
-        self.builder = self.builder.version(version);
+        self.version = Some(version);
         self
     }
 
-    /// Add an object store to the database.
+    /// Add an object store to the database. The object store and its indexes are created when the database is
+    /// upgraded, if they don't exist already.
+    ///
+    /// Unless migrations are added (see [`migration`](Self::migration)), the declared object stores are the complete
+    /// schema of the database: when it is upgraded, object stores and indexes which are not declared are deleted
+    /// (after the upgrade callback has run).
     pub fn add_object_store(mut self, object_store: ObjectStore) -> Self {
-        self.builder = self.builder.add_object_store(object_store.builder);
+        self.object_stores
+            .retain(|existing| existing.name != object_store.name);
+        self.object_stores.push(object_store);
+        self
+    }
+
+    /// Delete an object store (along with all its records) from the database when it is upgraded. Object stores are
+    /// deleted before the declared object stores are created, so, if an object store with the same name is also added,
+    /// it is re-created.
+    pub fn delete_object_store(mut self, name: &str) -> Self {
+        self.object_stores_to_delete.push(name.to_owned());
+        self
+    }
+
+    /// Add a migration step which upgrades the database to given version. When the database is upgraded, migrations
+    /// with versions greater than the version stored in the browser (see [`Rexie::version`]) and less than or equal to
+    /// the target version are run in ascending order of their versions, after the declared object stores have been
+    /// deleted and created. So, users who skip several releases go through every intermediate step in one upgrade.
+    ///
+    /// The migration receives an [`Upgrade`] handle which can be used to add and delete object stores and indexes,
+    /// and to rewrite records. If a migration returns an error, the whole upgrade is aborted and
+    /// [`build`](Self::build) returns that error. Adding another migration for the same version replaces it.
+    pub fn migration<F, Fut>(mut self, version: u32, migration: F) -> Self
+    where
+        F: FnOnce(Upgrade) -> Fut + 'static,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        self.migrations.insert(
+            version,
+            Box::new(move |upgrade| Box::pin(migration(upgrade))),
+        );
+        self
+    }
+
+    /// Specify a callback which is invoked when the database is upgraded, i.e., when it is created or its version is
+    /// bumped. The callback runs after the declared object stores have been deleted and created and all the
+    /// applicable migrations have run, and receives an [`Upgrade`] handle which can be used to read and write records
+    /// while the upgrade is running. If the callback returns an error, the upgrade is aborted and
+    /// [`build`](Self::build) returns that error.
+    pub fn on_upgrade<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(Upgrade) -> Fut + 'static,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        self.upgrade_callback = Some(Box::new(move |upgrade| Box::pin(callback(upgrade))));
+        self
+    }
+
+    /// Specify whether the database should be upgraded automatically when the declared object stores and indexes
+    /// differ from the ones already in the database (see [`Schema::diff`](crate::Schema::diff)).
+    ///
+    /// When enabled, the declared object stores are treated as the complete schema of the database: object stores and
+    /// indexes which are not declared are deleted, and object stores whose key path or auto increment flag differ are
+    /// re-created (their records are lost). If a version is specified, it is checked against the version in the
+    /// browser and [`build`](Self::build) fails with [`Error::VersionNotBumped`] if the schema differs but the version
+    /// isn't greater. Otherwise, the version is derived automatically by bumping the current version only when the
+    /// schema differs.
+    pub fn auto_upgrade(mut self, auto_upgrade: bool) -> Self {
+        self.auto_upgrade = auto_upgrade;
+        self
+    }
+
+    /// Specify a callback which is invoked (with the old and the new version) when opening the database is blocked,
+    /// i.e., when other connections to the database (for example, in other tabs) don't close after being notified of
+    /// the version change. [`build`](Self::build) keeps waiting until those connections are closed.
+    ///
+    /// If no callback is specified, [`build`](Self::build) fails with [`Error::Blocked`] instead of waiting, and the
+    /// pending upgrade is cancelled.
+    pub fn on_blocked<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(u32, u32) + 'static,
+    {
+        self.blocked_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Specify a callback which is invoked when another connection (for example, in another tab) wants to upgrade
+    /// (the new version is `Some`) or delete (the new version is `None`) the database.
+    ///
+    /// To not block the other connection, the connection is always closed before invoking the callback, after which
+    /// creating a new transaction on the [`Rexie`] fails with [`Error::VersionChanged`]. The callback can be used to
+    /// notify the user or to re-open the database.
+    pub fn on_version_change<F>(mut self, callback: F) -> Self
+    where
+        F: FnOnce(u32, Option<u32>) + 'static,
+    {
+        self.version_change_callback = Some(Box::new(callback));
         self
     }
 
     /// Build the database.
-    pub async fn build(self) -> Result<Rexie> {
-        let database = self.builder.build().await?;
-        Ok(Rexie { database })
+    pub async fn build(mut self) -> Result<Rexie> {
+        let factory = Factory::new()?;
+
+        let mut version = self
+            .version
+            .or_else(|| self.migrations.keys().next_back().copied());
+
+        if self.auto_upgrade {
+            version = Some(self.auto_version(&factory, version).await?);
+        }
+
+        let mut request = factory.open(&self.name, version)?;
+
+        let upgrade_error = Rc::new(RefCell::new(None));
+        let cancelled = Rc::new(Cell::new(false));
+
+        let version_attributes = self.version_attributes();
+
+        let steps = UpgradeSteps {
+            object_stores: self.object_stores,
+            object_stores_to_delete: self.object_stores_to_delete,
+            migrations: self.migrations,
+            upgrade_callback: self.upgrade_callback,
+            version_attributes: version_attributes.clone(),
+        };
+        let upgrade_error_slot = upgrade_error.clone();
+        let cancelled_flag = cancelled.clone();
+
+        request
+            .on_upgrade_needed(move |event| steps.run(event, upgrade_error_slot, cancelled_flag));
+
+        let blocked = Rc::new(BlockedSignal::default());
+
+        match self.blocked_callback {
+            Some(blocked_callback) => request.on_blocked(move |event| {
+                let old_version = event.old_version().unwrap_or_default();
+                let new_version = event.new_version().ok().flatten().unwrap_or_default();
+                blocked_callback(old_version, new_version);
+            }),
+            None => {
+                let blocked = blocked.clone();
+                request.on_blocked(move |_| blocked.set());
+            }
+        }
+
+        let mut request = request.into_future();
+
+        let result = poll_fn(|cx| match Pin::new(&mut request).poll(cx) {
+            Poll::Ready(result) => Poll::Ready(result.map_err(Into::into)),
+            Poll::Pending if blocked.is_set() => Poll::Ready(Err(Error::Blocked)),
+            Poll::Pending => {
+                blocked.register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await;
+
+        if matches!(result, Err(Error::Blocked)) {
+            // Cancels the upgrade (if it runs after other connections are closed). The request stays pending, so it is
+            // awaited in the background to close the connection in case the database is opened anyway (for example,
+            // when another connection has upgraded it in the meantime).
+            cancelled.set(true);
+
+            spawn_local(async move {
+                if let Ok(database) = request.await {
+                    database.close();
+                }
+            });
+
+            return Err(Error::Blocked);
+        }
+
+        let upgrade_error = upgrade_error.borrow_mut().take();
+
+        if let Some(err) = upgrade_error {
+            if let Ok(database) = result {
+                database.close();
+            }
+
+            return Err(err);
+        }
+
+        let mut rexie = connect(result?, self.version_change_callback);
+        rexie.version_attributes = version_attributes;
+
+        Ok(rexie)
+    }
+
+    /// Open the database at its current version without upgrading it. Migrations and upgrade callbacks are ignored,
+    /// and declared object stores are only used for their version attributes (see
+    /// [`ObjectStore::version_attribute`]). Fails with [`Error::DatabaseNotFound`] if the database doesn't exist.
+    pub async fn open_existing(self) -> Result<Rexie> {
+        let factory = Factory::new()?;
+        let version_attributes = self.version_attributes();
+
+        match open_existing(&factory, &self.name).await? {
+            Some(database) => {
+                let mut rexie = connect(database, self.version_change_callback);
+                rexie.version_attributes = version_attributes;
+
+                Ok(rexie)
+            }
+            None => Err(Error::DatabaseNotFound(self.name)),
+        }
+    }
+
+    /// Returns the version attributes of the declared object stores (by store name)
+    fn version_attributes(&self) -> Rc<HashMap<String, String>> {
+        let version_attributes = self
+            .object_stores
+            .iter()
+            .filter_map(|object_store| {
+                let attribute = object_store.version_attribute.clone()?;
+                Some((object_store.name.clone(), attribute))
+            })
+            .collect();
+
+        Rc::new(version_attributes)
+    }
+
+    /// Compares the declared object stores with the schema in the browser and returns the version to open the
+    /// database with. Object stores and indexes which need to be deleted for the upgrade are recorded in the builder.
+    async fn auto_version(&mut self, factory: &Factory, version: Option<u32>) -> Result<u32> {
+        let declared: Vec<StoreSchema> = self.object_stores.iter().map(Into::into).collect();
+
+        let (current_version, diff) = match open_existing(factory, &self.name).await? {
+            None => return Ok(version.unwrap_or(1)),
+            Some(database) => {
+                let rexie = Rexie::new(database);
+                let schema = rexie.schema();
+                rexie.close();
+
+                let schema = schema?;
+                (schema.version, schema.diff(&declared))
+            }
+        };
+
+        if diff.is_empty() {
+            return Ok(version.unwrap_or(current_version));
+        }
+
+        let version = match version {
+            None => current_version + 1,
+            Some(version) if version > current_version => version,
+            Some(version) => return Err(Error::VersionNotBumped(version, current_version)),
+        };
+
+        self.object_stores_to_delete
+            .extend(diff.removed_stores.into_iter().chain(diff.changed_stores));
+
+        for (store_name, index_name) in diff.removed_indexes {
+            if let Some(object_store) = self
+                .object_stores
+                .iter_mut()
+                .find(|object_store| object_store.name == store_name)
+            {
+                object_store.indexes_to_delete.push(index_name);
+            }
+        }
+
+        Ok(version)
     }
 
     /// Delete the database. Note that the future returned by this function doesn't reach completion until the database
@@ -42,3 +322,216 @@ impl RexieBuilder {
         factory.delete(&self.name)?.await.map_err(Into::into)
     }
 }
+
+/// Wraps an open database connection, closing it when another connection wants to upgrade or delete the database.
+fn connect(database: Database, version_change_callback: Option<VersionChangeCallback>) -> Rexie {
+    let mut rexie = Rexie::new(database);
+    let version_changed = rexie.version_changed.clone();
+
+    rexie.database.on_version_change(move |event| {
+        if let Ok(database) = event.database() {
+            database.close();
+        }
+
+        version_changed.set(true);
+
+        if let Some(version_change_callback) = version_change_callback {
+            if let Ok(event) = VersionChangeEvent::try_from(JsValue::from(event)) {
+                let old_version = event.old_version().unwrap_or_default();
+                let new_version = event.new_version().ok().flatten();
+                version_change_callback(old_version, new_version);
+            }
+        }
+    });
+
+    rexie
+}
+
+/// Opens an existing database at its current version. Returns `None` if the database doesn't exist.
+async fn open_existing(factory: &Factory, name: &str) -> Result<Option<Database>> {
+    let mut request = factory.open(name, None)?;
+
+    let created = Rc::new(Cell::new(false));
+    let created_flag = created.clone();
+
+    // `upgradeneeded` is fired (without specifying a version) only when the database doesn't exist. Aborting the upgrade
+    // transaction makes sure that the database isn't created.
+    request.on_upgrade_needed(move |event| {
+        created_flag.set(true);
+
+        if let Some(transaction) = event
+            .target()
+            .ok()
+            .and_then(|request| request.transaction())
+        {
+            let _ = transaction.abort();
+        }
+    });
+
+    match request.await {
+        Ok(database) => Ok(Some(database)),
+        Err(_) if created.get() => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Signal which is set when an open request is blocked, waking up the task waiting for the request.
+#[derive(Default)]
+struct BlockedSignal {
+    blocked: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl BlockedSignal {
+    fn set(&self) {
+        self.blocked.set(true);
+
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+
+    fn is_set(&self) -> bool {
+        self.blocked.get()
+    }
+
+    fn register(&self, waker: &Waker) {
+        *self.waker.borrow_mut() = Some(waker.clone());
+    }
+}
+
+/// Steps which are run (in order) when the database is upgraded.
+struct UpgradeSteps {
+    object_stores: Vec<ObjectStore>,
+    object_stores_to_delete: Vec<String>,
+    migrations: BTreeMap<u32, UpgradeCallback>,
+    upgrade_callback: Option<UpgradeCallback>,
+    version_attributes: Rc<HashMap<String, String>>,
+}
+
+impl UpgradeSteps {
+    /// Handles `upgradeneeded` event of an open request.
+    fn run(
+        self,
+        event: VersionChangeEvent,
+        upgrade_error: Rc<RefCell<Option<Error>>>,
+        cancelled: Rc<Cell<bool>>,
+    ) {
+        if cancelled.get() {
+            // `build` has already failed (it was blocked by other connections)
+            return abort_transaction(&event);
+        }
+
+        // With migrations, the schema is managed by the migrations, so undeclared object stores and indexes are kept
+        let delete_undeclared = self.migrations.is_empty();
+        let declared_store_names: Option<Vec<String>> = delete_undeclared.then(|| {
+            self.object_stores
+                .iter()
+                .map(|object_store| object_store.name.clone())
+                .collect()
+        });
+
+        let version_attributes = self.version_attributes;
+
+        let result = Upgrade::new(&event, version_attributes.clone()).and_then(|upgrade| {
+            let store_names = upgrade.store_names();
+
+            for store_name in self.object_stores_to_delete {
+                if store_names.contains(&store_name) {
+                    upgrade.delete_object_store(&store_name)?;
+                }
+            }
+
+            for object_store in self.object_stores {
+                upgrade.apply_object_store(object_store, delete_undeclared)?;
+            }
+
+            Ok(upgrade)
+        });
+
+        let upgrade = match result {
+            Ok(upgrade) => upgrade,
+            Err(err) => return abort_upgrade(&event, err, &upgrade_error),
+        };
+
+        let old_version = upgrade.old_version();
+        let new_version = upgrade.new_version();
+
+        let callbacks = self
+            .migrations
+            .into_iter()
+            .filter(|(version, _)| *version > old_version && *version <= new_version)
+            .map(|(_, migration)| migration)
+            .chain(self.upgrade_callback)
+            .map(|callback| {
+                Upgrade::new(&event, version_attributes.clone()).map(|upgrade| (callback, upgrade))
+            })
+            .collect::<Result<Vec<_>>>();
+
+        let callbacks = match callbacks {
+            Ok(callbacks) if callbacks.is_empty() => {
+                if let Err(err) = delete_undeclared_object_stores(&event, &declared_store_names) {
+                    abort_upgrade(&event, err, &upgrade_error);
+                }
+
+                return;
+            }
+            Ok(callbacks) => callbacks,
+            Err(err) => return abort_upgrade(&event, err, &upgrade_error),
+        };
+
+        spawn_local(async move {
+            for (callback, upgrade) in callbacks {
+                if let Err(err) = callback(upgrade).await {
+                    return abort_upgrade(&event, err, &upgrade_error);
+                }
+            }
+
+            if let Err(err) = delete_undeclared_object_stores(&event, &declared_store_names) {
+                abort_upgrade(&event, err, &upgrade_error);
+            }
+        });
+    }
+}
+
+/// Deletes the object stores which exist in the database but are not declared (if `declared_store_names` is `Some`)
+fn delete_undeclared_object_stores(
+    event: &VersionChangeEvent,
+    declared_store_names: &Option<Vec<String>>,
+) -> Result<()> {
+    let declared_store_names = match declared_store_names {
+        Some(declared_store_names) => declared_store_names,
+        None => return Ok(()),
+    };
+
+    let database = event.database()?;
+
+    for store_name in database.store_names() {
+        if !declared_store_names.contains(&store_name) {
+            database.delete_object_store(&store_name)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the error and aborts the upgrade transaction so that the open request fails.
+fn abort_upgrade(
+    event: &VersionChangeEvent,
+    err: Error,
+    upgrade_error: &Rc<RefCell<Option<Error>>>,
+) {
+    upgrade_error.borrow_mut().get_or_insert(err);
+    abort_transaction(event);
+}
+
+/// Aborts the upgrade transaction of an `upgradeneeded` event.
+fn abort_transaction(event: &VersionChangeEvent) {
+    if let Some(transaction) = event
+        .target()
+        .ok()
+        .and_then(|request| request.transaction())
+    {
+        let _ = transaction.abort();
+    }
+}
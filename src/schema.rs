@@ -0,0 +1,259 @@
+use idb::{Index as IdbIndex, ObjectStore as IdbObjectStore};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Index, KeyPath, ObjectStore, Result};
+
+/// Snapshot of the schema of a database (see [`Rexie::schema`](crate::Rexie::schema)).
+///
+/// With the `serde` feature enabled, the schema can be serialized (for example, to log it in bug reports).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Schema {
+    /// Name of the database
+    pub name: String,
+    /// Version of the database
+    pub version: u32,
+    /// Object stores in the database (sorted by name)
+    pub stores: Vec<StoreSchema>,
+}
+
+impl Schema {
+    /// Returns the schema of the object store with given name
+    pub fn store(&self, name: &str) -> Option<&StoreSchema> {
+        self.stores.iter().find(|store| store.name == name)
+    }
+
+    /// Compares the schema with given (declared) object stores and returns the changes needed to turn this schema into
+    /// the declared one.
+    pub fn diff(&self, stores: &[StoreSchema]) -> SchemaDiff {
+        let mut diff = SchemaDiff::default();
+
+        for existing_store in self.stores.iter() {
+            if !stores.iter().any(|store| store.name == existing_store.name) {
+                diff.removed_stores.push(existing_store.name.clone());
+            }
+        }
+
+        for store in stores {
+            let existing_store = match self.store(&store.name) {
+                None => {
+                    diff.added_stores.push(store.name.clone());
+                    continue;
+                }
+                Some(existing_store) => existing_store,
+            };
+
+            if existing_store.key_path != store.key_path
+                || existing_store.auto_increment != store.auto_increment
+            {
+                diff.changed_stores.push(store.name.clone());
+                continue;
+            }
+
+            for existing_index in existing_store.indexes.iter() {
+                if store.index(&existing_index.name).is_none() {
+                    diff.removed_indexes
+                        .push((store.name.clone(), existing_index.name.clone()));
+                }
+            }
+
+            for index in store.indexes.iter() {
+                match existing_store.index(&index.name) {
+                    None => diff
+                        .added_indexes
+                        .push((store.name.clone(), index.name.clone())),
+                    Some(existing_index) if existing_index != index => diff
+                        .changed_indexes
+                        .push((store.name.clone(), index.name.clone())),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        diff
+    }
+}
+
+/// Differences between two schemas (see [`Schema::diff`]). Indexes are identified by `(store name, index name)` pairs
+/// and only listed for object stores which exist in both the schemas and are not changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchemaDiff {
+    /// Object stores which need to be created
+    pub added_stores: Vec<String>,
+    /// Object stores which need to be deleted
+    pub removed_stores: Vec<String>,
+    /// Object stores whose key path or auto increment flag differ, i.e., which need to be re-created
+    pub changed_stores: Vec<String>,
+    /// Indexes which need to be created
+    pub added_indexes: Vec<(String, String)>,
+    /// Indexes which need to be deleted
+    pub removed_indexes: Vec<(String, String)>,
+    /// Indexes whose key path or flags differ, i.e., which need to be re-created
+    pub changed_indexes: Vec<(String, String)>,
+}
+
+impl SchemaDiff {
+    /// Returns `true` if there are no differences between the schemas
+    pub fn is_empty(&self) -> bool {
+        self.added_stores.is_empty()
+            && self.removed_stores.is_empty()
+            && self.changed_stores.is_empty()
+            && self.added_indexes.is_empty()
+            && self.removed_indexes.is_empty()
+            && self.changed_indexes.is_empty()
+    }
+}
+
+/// Schema of an object store
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StoreSchema {
+    /// Name of the object store
+    pub name: String,
+    /// Key path of the object store (`None` if the object store uses out-of-line keys)
+    #[cfg_attr(feature = "serde", serde(with = "serde_key_path::option"))]
+    pub key_path: Option<KeyPath>,
+    /// Whether the object store has auto increment enabled
+    pub auto_increment: bool,
+    /// Indexes of the object store (sorted by name)
+    pub indexes: Vec<IndexSchema>,
+}
+
+impl StoreSchema {
+    /// Returns the schema of the index with given name
+    pub fn index(&self, name: &str) -> Option<&IndexSchema> {
+        self.indexes.iter().find(|index| index.name == name)
+    }
+
+    pub(crate) fn new(object_store: &IdbObjectStore) -> Result<Self> {
+        let indexes = object_store
+            .index_names()
+            .iter()
+            .map(|index_name| IndexSchema::new(&object_store.index(index_name)?))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            name: object_store.name(),
+            key_path: object_store.key_path()?,
+            auto_increment: object_store.auto_increment(),
+            indexes,
+        })
+    }
+}
+
+impl From<&ObjectStore> for StoreSchema {
+    fn from(object_store: &ObjectStore) -> Self {
+        let mut indexes: Vec<IndexSchema> = object_store.indexes.iter().map(Into::into).collect();
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            name: object_store.name.clone(),
+            key_path: object_store.key_path.clone(),
+            auto_increment: object_store.auto_increment,
+            indexes,
+        }
+    }
+}
+
+/// Schema of an index
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndexSchema {
+    /// Name of the index
+    pub name: String,
+    /// Key path of the index
+    #[cfg_attr(feature = "serde", serde(with = "serde_key_path"))]
+    pub key_path: KeyPath,
+    /// Whether the index has unique enabled
+    pub unique: bool,
+    /// Whether the index has multi entry enabled
+    pub multi_entry: bool,
+}
+
+impl IndexSchema {
+    pub(crate) fn new(index: &IdbIndex) -> Result<Self> {
+        Ok(Self {
+            name: index.name(),
+            key_path: index.key_path()?.ok_or(idb::Error::InvalidKeyPath)?,
+            unique: index.unique(),
+            multi_entry: index.multi_entry(),
+        })
+    }
+}
+
+impl From<&Index> for IndexSchema {
+    fn from(index: &Index) -> Self {
+        Self {
+            name: index.name.clone(),
+            key_path: index.key_path.clone(),
+            unique: index.unique,
+            multi_entry: index.multi_entry,
+        }
+    }
+}
+
+/// (De)serializes a [`KeyPath`] as a string (single key path) or an array of strings (key path array).
+#[cfg(feature = "serde")]
+mod serde_key_path {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::KeyPath;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum KeyPathDef {
+        Single(String),
+        Array(Vec<String>),
+    }
+
+    impl From<KeyPathDef> for KeyPath {
+        fn from(key_path: KeyPathDef) -> Self {
+            match key_path {
+                KeyPathDef::Single(key_path) => KeyPath::Single(key_path),
+                KeyPathDef::Array(key_path_array) => KeyPath::Array(key_path_array),
+            }
+        }
+    }
+
+    impl From<&KeyPath> for KeyPathDef {
+        fn from(key_path: &KeyPath) -> Self {
+            match key_path {
+                KeyPath::Single(key_path) => KeyPathDef::Single(key_path.clone()),
+                KeyPath::Array(key_path_array) => KeyPathDef::Array(key_path_array.clone()),
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(key_path: &KeyPath, serializer: S) -> Result<S::Ok, S::Error> {
+        KeyPathDef::from(key_path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyPath, D::Error> {
+        KeyPathDef::deserialize(deserializer).map(Into::into)
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use super::KeyPathDef;
+        use crate::KeyPath;
+
+        pub fn serialize<S: Serializer>(
+            key_path: &Option<KeyPath>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            key_path
+                .as_ref()
+                .map(KeyPathDef::from)
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<KeyPath>, D::Error> {
+            Option::<KeyPathDef>::deserialize(deserializer).map(|key_path| key_path.map(Into::into))
+        }
+    }
+}
@@ -1,7 +1,21 @@
+mod batch;
+mod cursor;
 mod index;
+mod page;
 mod store;
+#[cfg(feature = "serde")]
+mod typed_store;
 
-pub use self::{index::StoreIndex, store::Store};
+#[cfg(feature = "serde")]
+pub use self::typed_store::TypedStore;
+pub use self::{
+    batch::BatchMode,
+    index::StoreIndex,
+    page::{Page, PageToken},
+    store::Store,
+};
+
+use std::{collections::HashMap, rc::Rc};
 
 use idb::Transaction as IdbTransaction;
 
@@ -10,6 +24,7 @@ use crate::{Error, Result, TransactionMode, TransactionResult};
 /// Transaction on the database
 pub struct Transaction {
     pub(crate) transaction: IdbTransaction,
+    pub(crate) version_attributes: Rc<HashMap<String, String>>,
 }
 
 impl Transaction {
@@ -61,7 +76,28 @@ impl Transaction {
     pub fn store(&self, store_name: &str) -> Result<Store> {
         self.transaction
             .object_store(store_name)
-            .map(|object_store| Store { object_store })
+            .map(|object_store| Store {
+                object_store,
+                version_attribute: self.version_attributes.get(store_name).cloned(),
+            })
             .map_err(Into::into)
     }
+
+    /// Returns a store in the transaction whose values are (de)serialized to and from `T`
+    #[cfg(feature = "serde")]
+    pub fn typed_store<T>(&self, store_name: &str) -> Result<TypedStore<T>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.store(store_name).map(TypedStore::new)
+    }
+
+    /// Returns the typed store of a [`Record`](crate::Record) in the transaction
+    #[cfg(feature = "serde")]
+    pub fn record_store<T>(&self) -> Result<TypedStore<T>>
+    where
+        T: crate::Record + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.typed_store(T::STORE_NAME)
+    }
 }
@@ -0,0 +1,150 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::poll_fn,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use idb::{ObjectStore, Request, StoreEvent};
+use wasm_bindgen::{JsCast, JsValue};
+
+use web_sys::DomException;
+
+use crate::{Error, Key, Result};
+
+/// Specifies how a batch of writes handles failed items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// First failed item aborts the transaction, i.e., the whole batch (along with all other writes in the transaction)
+    /// is rolled back
+    Abort,
+    /// Failed items are skipped and the rest of the batch is written
+    Skip,
+}
+
+/// Results of the writes of a batch (in input order)
+#[derive(Default)]
+struct BatchState {
+    results: RefCell<Vec<Option<Result<Key>>>>,
+    pending: Cell<usize>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl BatchState {
+    fn complete(&self, index: usize, result: Result<Key>) {
+        self.results.borrow_mut()[index] = Some(result);
+        self.pending.set(self.pending.get() - 1);
+
+        if self.pending.get() == 0 {
+            if let Some(waker) = self.waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Writes all the key value pairs using `write` and returns the key (or the error) of each pair in input order.
+pub(crate) async fn write_batch<R, W>(
+    object_store: &ObjectStore,
+    iter: impl Iterator<Item = (JsValue, Option<Key>)>,
+    mode: BatchMode,
+    write: W,
+) -> Vec<Result<Key>>
+where
+    R: Request,
+    R::Event: StoreEvent<Output = JsValue> + Into<JsValue>,
+    W: Fn(&ObjectStore, &JsValue, Option<&JsValue>) -> std::result::Result<R, idb::Error>,
+{
+    let state = Rc::new(BatchState::default());
+    // Requests own their callbacks, so they're kept until all of them are complete
+    let mut requests = Vec::new();
+    let mut aborted = false;
+
+    for (index, (value, key)) in iter.enumerate() {
+        if aborted {
+            // Pairs after a failed pair are not written, but still get a result so that results match the input
+            state.results.borrow_mut().push(Some(Err(abort_error())));
+            continue;
+        }
+
+        state.results.borrow_mut().push(None);
+
+        let key = key.map(JsValue::from);
+
+        let mut request = match write(object_store, &value, key.as_ref()) {
+            Ok(request) => request,
+            Err(err) => {
+                state.results.borrow_mut()[index] = Some(Err(err.into()));
+
+                if mode == BatchMode::Abort {
+                    let _ = object_store.transaction().abort();
+                    aborted = true;
+                }
+
+                continue;
+            }
+        };
+
+        state.pending.set(state.pending.get() + 1);
+
+        let success_state = state.clone();
+        request.on_success(move |event| {
+            let result = event.result().map_err(Into::into).and_then(Key::try_from);
+            success_state.complete(index, result);
+        });
+
+        let error_state = state.clone();
+        request.on_error(move |event| {
+            let err = event.error();
+
+            if mode == BatchMode::Skip {
+                // Prevents the failed request from aborting the transaction
+                let event: JsValue = event.into();
+                event.unchecked_into::<web_sys::Event>().prevent_default();
+            }
+
+            error_state.complete(index, Err(err.into()));
+        });
+
+        requests.push(request);
+    }
+
+    poll_fn(|cx| {
+        if state.pending.get() == 0 {
+            Poll::Ready(())
+        } else {
+            *state.waker.borrow_mut() = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    })
+    .await;
+
+    drop(requests);
+
+    let results: Vec<Result<Key>> = state
+        .results
+        .take()
+        .into_iter()
+        .map(|result| result.expect("all the writes of the batch are complete"))
+        .collect();
+
+    if mode == BatchMode::Abort && results.iter().any(Result::is_err) {
+        // The transaction was aborted, so the pairs which were written before the failure were rolled back as well
+        return results
+            .into_iter()
+            .map(|result| result.and_then(|_| Err(abort_error())))
+            .collect();
+    }
+
+    results
+}
+
+/// Error of a write which was not made because the transaction was aborted (same as the error of a pending request of
+/// an aborted transaction)
+fn abort_error() -> Error {
+    let exception =
+        DomException::new_with_message_and_name("The transaction was aborted.", "AbortError")
+            .unwrap_or_else(|err| err.unchecked_into());
+
+    idb::Error::DomException(exception).into()
+}
@@ -0,0 +1,138 @@
+use futures_core::Stream;
+use futures_util::stream::unfold;
+use idb::{request::futures::OpenCursorStoreRequestFuture, ManagedCursor, ManagedKeyCursor};
+use wasm_bindgen::JsValue;
+
+use crate::{Key, Result};
+
+/// State of a cursor stream
+enum CursorState {
+    /// Cursor is being opened
+    Opening(OpenCursorStoreRequestFuture),
+    /// Cursor points at a record which has already been yielded
+    Open(ManagedCursor),
+    /// Cursor is finished (or failed)
+    Done,
+}
+
+/// Returns a stream of key-value pairs over a cursor. The cursor is advanced only when the next pair is polled.
+pub(crate) fn cursor_stream(
+    request: OpenCursorStoreRequestFuture,
+) -> impl Stream<Item = Result<(Key, JsValue)>> {
+    unfold(CursorState::Opening(request), |state| async move {
+        let cursor = match state {
+            CursorState::Opening(request) => match request.await {
+                Ok(Some(cursor)) => cursor.into_managed(),
+                Ok(None) => return None,
+                Err(err) => return Some((Err(err.into()), CursorState::Done)),
+            },
+            CursorState::Open(mut cursor) => match cursor.next(None).await {
+                Ok(()) => cursor,
+                Err(err) => return Some((Err(err.into()), CursorState::Done)),
+            },
+            CursorState::Done => return None,
+        };
+
+        match (cursor.key(), cursor.value()) {
+            (Ok(Some(key)), Ok(Some(value))) => Some((
+                Key::try_from(key).map(|key| (key, value)),
+                CursorState::Open(cursor),
+            )),
+            (Err(err), _) | (_, Err(err)) => Some((Err(err.into()), CursorState::Done)),
+            _ => None,
+        }
+    })
+}
+
+/// Collects `(key, primary key)` pairs from a key cursor, skipping `offset` pairs and collecting at most `limit` pairs.
+pub(crate) async fn collect_keys(
+    mut cursor: ManagedKeyCursor,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<(Key, Key)>> {
+    if let Some(offset) = offset.filter(|offset| *offset > 0) {
+        cursor.advance(offset).await?;
+    }
+
+    let mut result = Vec::new();
+
+    while limit.is_none_or(|limit| result.len() < limit as usize) {
+        match (cursor.key()?, cursor.primary_key()?) {
+            (Some(key), Some(primary_key)) => {
+                result.push((key.try_into()?, primary_key.try_into()?));
+                cursor.next(None).await?;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(result)
+}
+
+/// Counts the remaining records of a key cursor
+pub(crate) async fn count_keys(mut cursor: ManagedKeyCursor) -> Result<u32> {
+    let mut count = 0;
+
+    while cursor.key()?.is_some() {
+        count += 1;
+        cursor.next(None).await?;
+    }
+
+    Ok(count)
+}
+
+/// Visits all records of a cursor, updating the records for which `update` returns a new value. Returns the number of
+/// updated records.
+pub(crate) async fn update_records<F>(
+    request: OpenCursorStoreRequestFuture,
+    mut update: F,
+) -> Result<u32>
+where
+    F: FnMut(&Key, JsValue) -> Option<JsValue>,
+{
+    let mut cursor = match request.await? {
+        Some(cursor) => cursor.into_managed(),
+        None => return Ok(0),
+    };
+
+    let mut updated = 0;
+
+    while let (Some(key), Some(value)) = (cursor.key()?, cursor.value()?) {
+        if let Some(value) = update(&key.try_into()?, value) {
+            cursor.update(&value).await?;
+            updated += 1;
+        }
+
+        cursor.next(None).await?;
+    }
+
+    Ok(updated)
+}
+
+/// Visits all records of a cursor, deleting the records for which `predicate` returns `true`. Returns the number of
+/// deleted records.
+pub(crate) async fn delete_records<F>(
+    request: OpenCursorStoreRequestFuture,
+    mut predicate: F,
+) -> Result<u32>
+where
+    F: FnMut(&Key, &JsValue) -> bool,
+{
+    let mut cursor = match request.await? {
+        Some(cursor) => cursor.into_managed(),
+        None => return Ok(0),
+    };
+
+    let mut deleted = 0;
+
+    while let (Some(key), Some(value)) = (cursor.key()?, cursor.value()?) {
+        if predicate(&key.try_into()?, &value) {
+            cursor.delete().await?;
+            deleted += 1;
+        }
+
+        cursor.next(None).await?;
+    }
+
+    Ok(deleted)
+}
@@ -1,7 +1,14 @@
+use std::future::IntoFuture;
+
+use futures_core::Stream;
 use idb::Index;
 use wasm_bindgen::JsValue;
 
-use crate::{Direction, KeyRange, Result};
+use super::{
+    cursor::{collect_keys, count_keys, cursor_stream, delete_records},
+    page::collect_page,
+};
+use crate::{Direction, Key, KeyRange, Page, PageToken, Result};
 
 /// Index of an object store.
 pub struct StoreIndex {
@@ -25,8 +32,35 @@ impl StoreIndex {
     }
 
     /// Gets a value from the store with given key
-    pub async fn get(&self, key: JsValue) -> Result<Option<JsValue>> {
-        self.index.get(key)?.await.map_err(Into::into)
+    pub async fn get(&self, key: impl Into<Key>) -> Result<Option<JsValue>> {
+        self.index
+            .get(JsValue::from(key.into()))?
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Gets the values with given keys from the index, aligned with the keys (`None` if there is no value for a
+    /// key). All the requests are issued at once instead of waiting for each value before requesting the next one.
+    pub async fn get_many<K: Into<Key>>(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<Vec<Option<JsValue>>> {
+        let requests = keys
+            .into_iter()
+            .map(|key| {
+                self.index
+                    .get(JsValue::from(key.into()))
+                    .map(IntoFuture::into_future)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut values = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            values.push(request.await?);
+        }
+
+        Ok(values)
     }
 
     /// Retrieves the keys of all objects inside the index
@@ -35,11 +69,13 @@ impl StoreIndex {
         &self,
         key_range: Option<KeyRange>,
         limit: Option<u32>,
-    ) -> Result<Vec<JsValue>> {
+    ) -> Result<Vec<Key>> {
         self.index
             .get_all_keys(key_range.map(Into::into), limit)?
-            .await
-            .map_err(Into::into)
+            .await?
+            .into_iter()
+            .map(Key::try_from)
+            .collect()
     }
 
     /// Gets all values from the store with given key range and limit
@@ -61,7 +97,7 @@ impl StoreIndex {
         limit: Option<u32>,
         offset: Option<u32>,
         direction: Option<Direction>,
-    ) -> Result<Vec<(JsValue, JsValue)>> {
+    ) -> Result<Vec<(Key, JsValue)>> {
         let cursor = self
             .index
             .open_cursor(key_range.map(Into::into), direction)?
@@ -86,7 +122,7 @@ impl StoreIndex {
 
                             match (key, value) {
                                 (Some(key), Some(value)) => {
-                                    result.push((key, value));
+                                    result.push((key.try_into()?, value));
                                     cursor.next(None).await?;
                                 }
                                 _ => break,
@@ -104,7 +140,7 @@ impl StoreIndex {
 
                             match (key, value) {
                                 (Some(key), Some(value)) => {
-                                    result.push((key, value));
+                                    result.push((key.try_into()?, value));
                                     cursor.next(None).await?;
                                 }
                                 _ => break,
@@ -118,6 +154,124 @@ impl StoreIndex {
         }
     }
 
+    /// Scans a page of at most `limit` key-value pairs from the index with given key range and direction, starting right
+    /// after the record pointed at by `token` (pass `None` to get the first page). Unlike the offset of
+    /// [`scan`](Self::scan), the token seeks directly to the next page, and pages don't shift when records are added
+    /// or deleted.
+    pub async fn scan_page(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: u32,
+        direction: Option<Direction>,
+        token: Option<PageToken>,
+    ) -> Result<Page> {
+        let request = self
+            .index
+            .open_cursor(key_range.map(Into::into), direction)?;
+        let by_primary_key = matches!(
+            direction,
+            None | Some(Direction::Next) | Some(Direction::Prev)
+        );
+
+        collect_page(
+            request.into_future(),
+            limit,
+            direction,
+            token,
+            by_primary_key,
+        )
+        .await
+    }
+
+    /// Scans all `(key, primary key)` pairs from the index with given key range, limit, offset and direction (note that
+    /// the key is the key of the index). Unlike [`scan`](Self::scan), values are not read.
+    pub async fn scan_keys(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        direction: Option<Direction>,
+    ) -> Result<Vec<(Key, Key)>> {
+        let cursor = self
+            .index
+            .open_key_cursor(key_range.map(Into::into), direction)?
+            .await?;
+
+        match cursor {
+            None => Ok(Vec::new()),
+            Some(cursor) => collect_keys(cursor.into_managed(), limit, offset).await,
+        }
+    }
+
+    /// Returns a stream of all key-value pairs from the index with given key range and direction. Unlike
+    /// [`scan`](Self::scan), the pairs are read lazily, i.e., the cursor is advanced only when the next pair is polled.
+    ///
+    /// # Note
+    ///
+    /// The stream keeps the transaction alive only as long as it is polled without awaiting anything else in between.
+    /// Once the transaction has no pending requests, it commits automatically and the stream yields an error.
+    pub fn stream(
+        &self,
+        key_range: Option<KeyRange>,
+        direction: Option<Direction>,
+    ) -> Result<impl Stream<Item = Result<(Key, JsValue)>>> {
+        let request = self
+            .index
+            .open_cursor(key_range.map(Into::into), direction)?;
+
+        Ok(cursor_stream(request.into_future()))
+    }
+
+    /// Returns the distinct keys of the index (in ascending order, or in descending order if `descending` is `true`)
+    /// with given key range and limit, i.e., each key is returned only once even if multiple records have the same
+    /// key. Values are not read.
+    pub async fn distinct_keys(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: Option<u32>,
+        descending: bool,
+    ) -> Result<Vec<Key>> {
+        let direction = if descending {
+            Direction::PrevUnique
+        } else {
+            Direction::NextUnique
+        };
+
+        let cursor = self
+            .index
+            .open_key_cursor(key_range.map(Into::into), Some(direction))?
+            .await?;
+
+        match cursor {
+            None => Ok(Vec::new()),
+            Some(cursor) => Ok(collect_keys(cursor.into_managed(), limit, None)
+                .await?
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect()),
+        }
+    }
+
+    /// Counts the number of distinct keys of the index with given key range
+    pub async fn count_distinct(&self, key_range: Option<KeyRange>) -> Result<u32> {
+        let cursor = self
+            .index
+            .open_key_cursor(key_range.map(Into::into), Some(Direction::NextUnique))?
+            .await?;
+
+        match cursor {
+            None => Ok(0),
+            Some(cursor) => count_keys(cursor.into_managed()).await,
+        }
+    }
+
+    /// Deletes all the records of the store which the index points to in given key range and returns the number of
+    /// deleted records
+    pub async fn delete_matching(&self, key_range: KeyRange) -> Result<u32> {
+        let request = self.index.open_cursor(Some(key_range.into()), None)?;
+        delete_records(request.into_future(), |_, _| true).await
+    }
+
     /// Counts the number of key value pairs in the store
     pub async fn count(&self, key_range: Option<KeyRange>) -> Result<u32> {
         self.index
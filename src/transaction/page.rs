@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+
+use idb::{request::futures::OpenCursorStoreRequestFuture, ManagedCursor};
+use wasm_bindgen::JsValue;
+
+use crate::{Direction, Key, Result};
+
+/// A page of key-value pairs returned by `scan_page` of [`Store`](crate::Store) and
+/// [`StoreIndex`](crate::StoreIndex).
+#[derive(Debug)]
+pub struct Page {
+    /// Key-value pairs of the page
+    pub items: Vec<(Key, JsValue)>,
+    /// Token to pass to `scan_page` to get the next page, or `None` if this is the last page
+    pub next: Option<PageToken>,
+}
+
+/// An opaque continuation token pointing at the last record of a [`Page`]. The next page starts right after this
+/// record, even if records are added or deleted in the meantime.
+///
+/// The token should be used with the same key range and direction as the page it was returned with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageToken {
+    key: Key,
+    primary_key: Key,
+}
+
+/// Reads a page of at most `limit` key-value pairs from a cursor, starting right after `token` (if any).
+///
+/// `by_primary_key` specifies whether the position of the token has to be resolved using the primary key as well,
+/// i.e., the cursor is opened on an index in a direction which doesn't skip duplicate keys.
+pub(crate) async fn collect_page(
+    request: OpenCursorStoreRequestFuture,
+    limit: u32,
+    direction: Option<Direction>,
+    token: Option<PageToken>,
+    by_primary_key: bool,
+) -> Result<Page> {
+    let mut cursor = match request.await? {
+        Some(cursor) => cursor.into_managed(),
+        None => return Ok(empty_page()),
+    };
+
+    if let Some(token) = token {
+        let reverse = matches!(
+            direction,
+            Some(Direction::Prev) | Some(Direction::PrevUnique)
+        );
+
+        if !seek(&mut cursor, &token, reverse, by_primary_key).await? {
+            return Ok(empty_page());
+        }
+    }
+
+    let mut items: Vec<(Key, JsValue)> = Vec::new();
+    let mut last_primary_key = None;
+
+    while items.len() < limit as usize {
+        match (cursor.key()?, cursor.value()?) {
+            (Some(key), Some(value)) => {
+                last_primary_key = cursor.primary_key()?;
+                items.push((key.try_into()?, value));
+                cursor.next(None).await?;
+            }
+            _ => break,
+        }
+    }
+
+    let has_next = cursor.key()?.is_some();
+
+    let next = match (items.last(), last_primary_key) {
+        (Some((key, _)), Some(primary_key)) if has_next => Some(PageToken {
+            key: key.clone(),
+            primary_key: primary_key.try_into()?,
+        }),
+        _ => None,
+    };
+
+    Ok(Page { items, next })
+}
+
+fn empty_page() -> Page {
+    Page {
+        items: Vec::new(),
+        next: None,
+    }
+}
+
+/// Moves the cursor to the first record after the token. Returns `false` if there is no such record.
+async fn seek(
+    cursor: &mut ManagedCursor,
+    token: &PageToken,
+    reverse: bool,
+    by_primary_key: bool,
+) -> Result<bool> {
+    // Compares the position of the cursor with the token in the direction of the cursor
+    let compare = |cursor: &ManagedCursor| -> Result<Option<Ordering>> {
+        let (key, primary_key) = match (cursor.key()?, cursor.primary_key()?) {
+            (Some(key), Some(primary_key)) => (Key::try_from(key)?, Key::try_from(primary_key)?),
+            _ => return Ok(None),
+        };
+
+        let mut ordering = key.partial_cmp(&token.key).unwrap_or(Ordering::Equal);
+
+        if by_primary_key && ordering == Ordering::Equal {
+            ordering = primary_key
+                .partial_cmp(&token.primary_key)
+                .unwrap_or(Ordering::Equal);
+        }
+
+        Ok(Some(if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }))
+    };
+
+    match compare(cursor)? {
+        None => return Ok(false),
+        Some(Ordering::Greater) => return Ok(true),
+        Some(Ordering::Equal) => cursor.next(None).await?,
+        Some(Ordering::Less) => {
+            let key = JsValue::from(&token.key);
+
+            if by_primary_key {
+                cursor
+                    .next_primary_key(&key, &JsValue::from(&token.primary_key))
+                    .await?;
+            } else {
+                cursor.next(Some(&key)).await?;
+            }
+
+            if compare(cursor)? == Some(Ordering::Equal) {
+                cursor.next(None).await?;
+            }
+        }
+    }
+
+    Ok(cursor.key()?.is_some())
+}
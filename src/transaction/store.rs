@@ -1,11 +1,23 @@
+use std::future::IntoFuture;
+
+use futures_core::Stream;
 use idb::ObjectStore;
-use wasm_bindgen::JsValue;
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
 
-use crate::{Direction, KeyPath, KeyRange, Result, StoreIndex};
+use super::{
+    batch::write_batch,
+    cursor::{collect_keys, cursor_stream, delete_records, update_records},
+    page::collect_page,
+};
+use crate::{
+    BatchMode, Direction, Error, Key, KeyPath, KeyRange, Page, PageToken, Result, StoreIndex,
+};
 
 /// An object store.
 pub struct Store {
     pub(crate) object_store: ObjectStore,
+    pub(crate) version_attribute: Option<String>,
 }
 
 impl Store {
@@ -42,20 +54,47 @@ impl Store {
 
     /// Gets a value from the store with given key
     /// MDN Reference: [IDBObjectStore/get](https://developer.mozilla.org/en-US/docs/Web/API/IDBObjectStore/get)
-    pub async fn get(&self, key: JsValue) -> Result<Option<JsValue>> {
-        self.object_store.get(key)?.await.map_err(Into::into)
+    pub async fn get(&self, key: impl Into<Key>) -> Result<Option<JsValue>> {
+        self.object_store
+            .get(JsValue::from(key.into()))?
+            .await
+            .map_err(Into::into)
     }
 
     /// Checks if a given key exists within the store
     /// MDN Reference: [IDBObjectStore/getKey](https://developer.mozilla.org/en-US/docs/Web/API/IDBObjectStore/getKey)
-    pub async fn key_exists(&self, key: JsValue) -> Result<bool> {
+    pub async fn key_exists(&self, key: impl Into<Key>) -> Result<bool> {
         self.object_store
-            .get_key(key)?
+            .get_key(JsValue::from(key.into()))?
             .await
             .map(|key| key.is_some())
             .map_err(Into::into)
     }
 
+    /// Gets the values with given keys from the store, aligned with the keys (`None` if there is no value for a
+    /// key). All the requests are issued at once instead of waiting for each value before requesting the next one.
+    pub async fn get_many<K: Into<Key>>(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<Vec<Option<JsValue>>> {
+        let requests = keys
+            .into_iter()
+            .map(|key| {
+                self.object_store
+                    .get(JsValue::from(key.into()))
+                    .map(IntoFuture::into_future)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut values = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            values.push(request.await?);
+        }
+
+        Ok(values)
+    }
+
     /// Retrieves record keys for all objects in the object store matching the specified
     /// parameter or all objects in the store if no parameters are given.
     /// MDN Reference: [IDBStore/getAllKeys](https://developer.mozilla.org/en-US/docs/Web/API/IDBStore/getAllKeys)
@@ -63,11 +102,13 @@ impl Store {
         &self,
         key_range: Option<KeyRange>,
         limit: Option<u32>,
-    ) -> Result<Vec<JsValue>> {
+    ) -> Result<Vec<Key>> {
         self.object_store
             .get_all_keys(key_range.map(Into::into), limit)?
-            .await
-            .map_err(Into::into)
+            .await?
+            .into_iter()
+            .map(Key::try_from)
+            .collect()
     }
 
     /// Gets all values from the store with given key range and limit
@@ -89,7 +130,7 @@ impl Store {
         limit: Option<u32>,
         offset: Option<u32>,
         direction: Option<Direction>,
-    ) -> Result<Vec<(JsValue, JsValue)>> {
+    ) -> Result<Vec<(Key, JsValue)>> {
         let cursor = self
             .object_store
             .open_cursor(key_range.map(Into::into), direction)?
@@ -114,7 +155,7 @@ impl Store {
 
                             match (key, value) {
                                 (Some(key), Some(value)) => {
-                                    result.push((key, value));
+                                    result.push((key.try_into()?, value));
                                     cursor.next(None).await?;
                                 }
                                 _ => break,
@@ -132,7 +173,7 @@ impl Store {
 
                             match (key, value) {
                                 (Some(key), Some(value)) => {
-                                    result.push((key, value));
+                                    result.push((key.try_into()?, value));
                                     cursor.next(None).await?;
                                 }
                                 _ => break,
@@ -146,21 +187,170 @@ impl Store {
         }
     }
 
+    /// Scans a page of at most `limit` key-value pairs from the store with given key range and direction, starting right
+    /// after the record pointed at by `token` (pass `None` to get the first page). Unlike the offset of
+    /// [`scan`](Self::scan), the token seeks directly to the next page, and pages don't shift when records are added
+    /// or deleted.
+    pub async fn scan_page(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: u32,
+        direction: Option<Direction>,
+        token: Option<PageToken>,
+    ) -> Result<Page> {
+        let request = self
+            .object_store
+            .open_cursor(key_range.map(Into::into), direction)?;
+        let by_primary_key = false;
+
+        collect_page(
+            request.into_future(),
+            limit,
+            direction,
+            token,
+            by_primary_key,
+        )
+        .await
+    }
+
+    /// Scans all `(key, primary key)` pairs from the store with given key range, limit, offset and direction (note that
+    /// the key and the primary key are the same for a store). Unlike [`scan`](Self::scan), values are not read.
+    pub async fn scan_keys(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        direction: Option<Direction>,
+    ) -> Result<Vec<(Key, Key)>> {
+        let cursor = self
+            .object_store
+            .open_key_cursor(key_range.map(Into::into), direction)?
+            .await?;
+
+        match cursor {
+            None => Ok(Vec::new()),
+            Some(cursor) => collect_keys(cursor.into_managed(), limit, offset).await,
+        }
+    }
+
+    /// Returns a stream of all key-value pairs from the store with given key range and direction. Unlike
+    /// [`scan`](Self::scan), the pairs are read lazily, i.e., the cursor is advanced only when the next pair is polled.
+    ///
+    /// # Note
+    ///
+    /// The stream keeps the transaction alive only as long as it is polled without awaiting anything else in between.
+    /// Once the transaction has no pending requests, it commits automatically and the stream yields an error.
+    pub fn stream(
+        &self,
+        key_range: Option<KeyRange>,
+        direction: Option<Direction>,
+    ) -> Result<impl Stream<Item = Result<(Key, JsValue)>>> {
+        let request = self
+            .object_store
+            .open_cursor(key_range.map(Into::into), direction)?;
+
+        Ok(cursor_stream(request.into_future()))
+    }
+
+    /// Updates the records in given key range in a single pass of a cursor. `update` is called with the key and the
+    /// value of each record and returns the new value of the record, or `None` to leave the record unchanged. Returns
+    /// the number of updated records.
+    ///
+    /// # Note
+    ///
+    /// The transaction should be `ReadWrite`. If updating a record fails, the error is returned and the remaining
+    /// records are not visited; abort the transaction to roll back the records which were already updated.
+    pub async fn update_where<F>(&self, key_range: Option<KeyRange>, update: F) -> Result<u32>
+    where
+        F: FnMut(&Key, JsValue) -> Option<JsValue>,
+    {
+        let request = self
+            .object_store
+            .open_cursor(key_range.map(Into::into), None)?;
+        update_records(request.into_future(), update).await
+    }
+
+    /// Deletes the records in given key range for which `predicate` (called with the key and the value of each record)
+    /// returns `true`, in a single pass of a cursor. Returns the number of deleted records.
+    ///
+    /// # Note
+    ///
+    /// The transaction should be `ReadWrite`. If deleting a record fails, the error is returned and the remaining
+    /// records are not visited; abort the transaction to roll back the records which were already deleted.
+    pub async fn delete_where<F>(&self, key_range: Option<KeyRange>, predicate: F) -> Result<u32>
+    where
+        F: FnMut(&Key, &JsValue) -> bool,
+    {
+        let request = self
+            .object_store
+            .open_cursor(key_range.map(Into::into), None)?;
+        delete_records(request.into_future(), predicate).await
+    }
+
+    /// Reads the value with given key, calls `update` with it (`None` if there is no value for the key) and writes back
+    /// the returned value, or deletes the record if `update` returns `None`. Returns the old and the new value.
+    ///
+    /// # Note
+    ///
+    /// The transaction should be `ReadWrite`. Since the read and the write happen in the same transaction, no other
+    /// transaction can modify the record in between. For stores with inline keys, the new value must have the same key
+    /// (otherwise [`Error::KeyChanged`] is returned and nothing is written).
+    pub async fn update<F>(
+        &self,
+        key: impl Into<Key>,
+        update: F,
+    ) -> Result<(Option<JsValue>, Option<JsValue>)>
+    where
+        F: FnOnce(Option<JsValue>) -> Option<JsValue>,
+    {
+        let key = key.into();
+        let js_key = JsValue::from(&key);
+        let old = self.object_store.get(js_key.clone())?.await?;
+        let new = update(old.clone());
+
+        match &new {
+            Some(value) => {
+                // Keys can't be passed explicitly to stores with inline keys, so the key of the new value is checked
+                // instead
+                let key = match self.object_store.key_path()? {
+                    Some(key_path) => {
+                        let new_key = Key::try_from(key_path_value(value, &key_path)).ok();
+
+                        if new_key.as_ref() != Some(&key) {
+                            return Err(Error::KeyChanged(key));
+                        }
+
+                        None
+                    }
+                    None => Some(&js_key),
+                };
+
+                self.object_store.put(value, key)?.await?;
+            }
+            None if old.is_some() => self.object_store.delete(js_key)?.await?,
+            None => {}
+        }
+
+        Ok((old, new))
+    }
+
     /// Adds a key value pair in the store. Note that the key can be `None` if store has auto increment enabled.
-    pub async fn add(&self, value: &JsValue, key: Option<&JsValue>) -> Result<JsValue> {
-        self.object_store.add(value, key)?.await.map_err(Into::into)
+    pub async fn add(&self, value: &JsValue, key: Option<Key>) -> Result<Key> {
+        let key = key.map(JsValue::from);
+        self.object_store
+            .add(value, key.as_ref())?
+            .await?
+            .try_into()
     }
 
     /// Adds all key value pairs (`(value, Option<key>)`) in the store. Note that the keys can be `None` if store has
     /// auto increment enabled.
-    pub async fn add_all(
-        &self,
-        iter: impl Iterator<Item = (JsValue, Option<JsValue>)>,
-    ) -> Result<()> {
+    pub async fn add_all(&self, iter: impl Iterator<Item = (JsValue, Option<Key>)>) -> Result<()> {
         let mut request = None;
 
         for (value, key) in iter {
-            request = Some(self.object_store.add(value.as_ref(), key.as_ref())?);
+            let key = key.map(JsValue::from);
+            request = Some(self.object_store.add(&value, key.as_ref())?);
         }
 
         if let Some(request) = request {
@@ -170,22 +360,98 @@ impl Store {
         }
     }
 
+    /// Adds all key value pairs (`(value, Option<key>)`) in the store and returns the key (or the error) of each pair
+    /// in input order. Note that the keys can be `None` if store has auto increment enabled.
+    ///
+    /// With [`BatchMode::Abort`], the first failed pair aborts the transaction, so the pairs which were added before it
+    /// are rolled back and every pair gets an error. With [`BatchMode::Skip`], failed pairs are skipped and
+    /// the rest of the pairs are added.
+    pub async fn try_add_all(
+        &self,
+        iter: impl Iterator<Item = (JsValue, Option<Key>)>,
+        mode: BatchMode,
+    ) -> Vec<Result<Key>> {
+        write_batch(&self.object_store, iter, mode, ObjectStore::add).await
+    }
+
     /// Puts (adds or updates) a key value pair in the store. Note that the keys can be `None` if store has auto
     /// increment enabled.
-    pub async fn put(&self, value: &JsValue, key: Option<&JsValue>) -> Result<JsValue> {
-        self.object_store.put(value, key)?.await.map_err(Into::into)
+    pub async fn put(&self, value: &JsValue, key: Option<Key>) -> Result<Key> {
+        let key = key.map(JsValue::from);
+        self.object_store
+            .put(value, key.as_ref())?
+            .await?
+            .try_into()
+    }
+
+    /// Puts (adds or updates) a key value pair in the store only if the version of the stored value equals `expected`
+    /// (a missing value or a value without version has version `0`). The version attribute of the value is set to
+    /// `expected + 1` before writing it, and the new version is returned. Fails with [`Error::VersionConflict`] if the
+    /// stored value has a different version, e.g., because another tab updated it in the meantime.
+    ///
+    /// # Note
+    ///
+    /// The store must have a version attribute (see [`ObjectStore::version_attribute`](crate::ObjectStore::version_attribute))
+    /// and the transaction should be `ReadWrite`. `value` must be an object (otherwise [`Error::NotAnObject`] is
+    /// returned) and is not modified itself. Fails with [`Error::VersionOverflow`] if `expected` is `u32::MAX`.
+    pub async fn put_if_version(
+        &self,
+        value: &JsValue,
+        key: Option<Key>,
+        expected: u32,
+    ) -> Result<u32> {
+        let attribute = self
+            .version_attribute
+            .as_deref()
+            .ok_or_else(|| Error::VersionAttributeMissing(self.name()))?;
+
+        if !value.is_object() || Array::is_array(value) {
+            return Err(Error::NotAnObject(value.clone()));
+        }
+
+        let version = expected
+            .checked_add(1)
+            .ok_or(Error::VersionOverflow(expected))?;
+
+        let lookup_key = match (&key, self.object_store.key_path()?) {
+            (Some(key), _) => JsValue::from(key),
+            (None, Some(key_path)) => key_path_value(value, &key_path),
+            (None, None) => JsValue::UNDEFINED,
+        };
+
+        // A value without key can't be stored yet (its key is generated by the store)
+        let stored = if lookup_key.is_undefined() {
+            None
+        } else {
+            self.object_store.get(lookup_key)?.await?
+        };
+
+        let found = match stored {
+            Some(stored) => version_of(&stored, attribute),
+            None => 0,
+        };
+
+        if found != expected {
+            return Err(Error::VersionConflict { expected, found });
+        }
+
+        let value = Object::assign(&Object::new(), value.unchecked_ref());
+        // Setting a property of a plain object can't fail
+        let _ = Reflect::set(&value, &JsValue::from_str(attribute), &version.into());
+
+        self.put(&value, key).await?;
+
+        Ok(version)
     }
 
     /// Puts (adds or updates) a key value pairs (`(value, Option<key>)`) in the store. Note that the keys can be `None`
     /// if store has auto increment enabled.
-    pub async fn put_all(
-        &self,
-        iter: impl Iterator<Item = (JsValue, Option<JsValue>)>,
-    ) -> Result<()> {
+    pub async fn put_all(&self, iter: impl Iterator<Item = (JsValue, Option<Key>)>) -> Result<()> {
         let mut request = None;
 
         for (value, key) in iter {
-            request = Some(self.object_store.put(value.as_ref(), key.as_ref())?);
+            let key = key.map(JsValue::from);
+            request = Some(self.object_store.put(&value, key.as_ref())?);
         }
 
         if let Some(request) = request {
@@ -195,9 +461,34 @@ impl Store {
         }
     }
 
+    /// Puts (adds or updates) all key value pairs (`(value, Option<key>)`) in the store and returns the key (or the
+    /// error) of each pair in input order. Note that the keys can be `None` if store has auto increment enabled.
+    ///
+    /// With [`BatchMode::Abort`], the first failed pair aborts the transaction, so the pairs which were put before it
+    /// are rolled back and every pair gets an error. With [`BatchMode::Skip`], failed pairs are skipped and
+    /// the rest of the pairs are put.
+    pub async fn try_put_all(
+        &self,
+        iter: impl Iterator<Item = (JsValue, Option<Key>)>,
+        mode: BatchMode,
+    ) -> Vec<Result<Key>> {
+        write_batch(&self.object_store, iter, mode, ObjectStore::put).await
+    }
+
     /// Deletes a key value pair from the store
-    pub async fn delete(&self, key: JsValue) -> Result<()> {
-        self.object_store.delete(key)?.await.map_err(Into::into)
+    pub async fn delete(&self, key: impl Into<Key>) -> Result<()> {
+        self.object_store
+            .delete(JsValue::from(key.into()))?
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Deletes all key value pairs in given key range from the store and returns the number of deleted pairs
+    pub async fn delete_range(&self, key_range: KeyRange) -> Result<u32> {
+        let count = self.count(Some(key_range.clone())).await?;
+        self.object_store.delete(key_range)?.await?;
+
+        Ok(count)
     }
 
     /// Counts the number of key value pairs in the store
@@ -213,3 +504,39 @@ impl Store {
         self.object_store.clear()?.await.map_err(Into::into)
     }
 }
+
+/// Reads the key of a value with given key path. Returns `undefined` if the value doesn't have the key.
+fn key_path_value(value: &JsValue, key_path: &KeyPath) -> JsValue {
+    let evaluate = |path: &String| {
+        path.split('.')
+            .filter(|name| !name.is_empty())
+            .fold(value.clone(), |value, name| {
+                if value.is_object() {
+                    Reflect::get(&value, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+                } else {
+                    JsValue::UNDEFINED
+                }
+            })
+    };
+
+    match key_path {
+        KeyPath::Single(path) => evaluate(path),
+        KeyPath::Array(paths) => {
+            let keys: Vec<JsValue> = paths.iter().map(evaluate).collect();
+
+            if keys.iter().any(JsValue::is_undefined) {
+                JsValue::UNDEFINED
+            } else {
+                keys.into_iter().collect::<Array>().into()
+            }
+        }
+    }
+}
+
+/// Reads the version attribute of a stored value (`0` if the value has no version)
+fn version_of(value: &JsValue, attribute: &str) -> u32 {
+    Reflect::get(value, &JsValue::from_str(attribute))
+        .ok()
+        .and_then(|version| version.as_f64())
+        .unwrap_or_default() as u32
+}
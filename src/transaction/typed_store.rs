@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{Direction, Error, Key, KeyRange, Result, Store};
+
+/// An object store whose values are (de)serialized to and from `T` using `serde`.
+///
+/// Values are serialized as plain JavaScript objects (maps are serialized as objects, not as `Map`s), so that key
+/// paths and indexes can refer to their fields.
+pub struct TypedStore<T> {
+    store: Store,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedStore<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub(crate) fn new(store: Store) -> Self {
+        Self {
+            store,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the underlying untyped store
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Gets a value from the store with given key
+    pub async fn get(&self, key: impl Into<Key>) -> Result<Option<T>> {
+        self.store.get(key).await?.map(from_value).transpose()
+    }
+
+    /// Gets all values from the store with given key range and limit
+    pub async fn get_all(&self, key_range: Option<KeyRange>, limit: Option<u32>) -> Result<Vec<T>> {
+        self.store
+            .get_all(key_range, limit)
+            .await?
+            .into_iter()
+            .map(from_value)
+            .collect()
+    }
+
+    /// Scans all key-value pairs from the store with given key range, limit, offset and direction
+    pub async fn scan(
+        &self,
+        key_range: Option<KeyRange>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+        direction: Option<Direction>,
+    ) -> Result<Vec<(Key, T)>> {
+        self.store
+            .scan(key_range, limit, offset, direction)
+            .await?
+            .into_iter()
+            .map(|(key, value)| Ok((key, from_value(value)?)))
+            .collect()
+    }
+
+    /// Adds a key value pair in the store. Note that the key can be `None` if store has auto increment enabled.
+    pub async fn add(&self, value: &T, key: Option<Key>) -> Result<Key> {
+        self.store.add(&to_value(value)?, key).await
+    }
+
+    /// Puts (adds or updates) a key value pair in the store. Note that the key can be `None` if store has auto
+    /// increment enabled.
+    pub async fn put(&self, value: &T, key: Option<Key>) -> Result<Key> {
+        self.store.put(&to_value(value)?, key).await
+    }
+
+    /// Deletes a key value pair from the store
+    pub async fn delete(&self, key: impl Into<Key>) -> Result<()> {
+        self.store.delete(key).await
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true))
+        .map_err(|err| Error::SerializationFailed(err.to_string()))
+}
+
+fn from_value<T: DeserializeOwned>(value: JsValue) -> Result<T> {
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|err| Error::DeserializationFailed(err.to_string()))
+}
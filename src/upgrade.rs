@@ -0,0 +1,93 @@
+use std::{collections::HashMap, rc::Rc};
+
+use idb::{event::VersionChangeEvent, Event, Request};
+
+use crate::{ObjectStore, Result, Transaction};
+
+/// Handle to a database while it is being upgraded (passed to [`RexieBuilder::migration`](crate::RexieBuilder::migration)
+/// and [`RexieBuilder::on_upgrade`](crate::RexieBuilder::on_upgrade)).
+pub struct Upgrade {
+    pub(crate) transaction: Transaction,
+    old_version: u32,
+    new_version: u32,
+}
+
+impl Upgrade {
+    /// Creates a new upgrade handle from an `upgradeneeded` event, with the version attributes of the declared object
+    /// stores
+    pub(crate) fn new(
+        event: &VersionChangeEvent,
+        version_attributes: Rc<HashMap<String, String>>,
+    ) -> Result<Self> {
+        let old_version = event.old_version()?;
+        let new_version = event.new_version()?.unwrap_or(old_version);
+
+        let transaction = event
+            .target()?
+            .transaction()
+            .ok_or(idb::Error::TransactionNotFound)?;
+
+        Ok(Self {
+            transaction: Transaction {
+                transaction,
+                version_attributes,
+            },
+            old_version,
+            new_version,
+        })
+    }
+
+    /// Returns the version of the database before the upgrade (`0` if the database is being created)
+    pub fn old_version(&self) -> u32 {
+        self.old_version
+    }
+
+    /// Returns the version of the database after the upgrade
+    pub fn new_version(&self) -> u32 {
+        self.new_version
+    }
+
+    /// Returns the `versionchange` transaction of the upgrade. It spans all the object stores in the database.
+    ///
+    /// # Note
+    ///
+    /// The transaction commits automatically as soon as there are no pending requests on it. So, only requests on
+    /// this transaction should be awaited while the upgrade is running.
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Returns names of all stores in the database
+    pub fn store_names(&self) -> Vec<String> {
+        self.transaction.transaction.database().store_names()
+    }
+
+    /// Adds an object store to the database. If the object store already exists, only its indexes are added or
+    /// deleted (as declared on the [`ObjectStore`]).
+    pub fn add_object_store(&self, object_store: ObjectStore) -> Result<()> {
+        self.apply_object_store(object_store, false)
+    }
+
+    /// Adds an object store to the database, optionally deleting the indexes which are not declared on it
+    pub(crate) fn apply_object_store(
+        &self,
+        object_store: ObjectStore,
+        delete_undeclared_indexes: bool,
+    ) -> Result<()> {
+        let transaction = &self.transaction.transaction;
+        object_store.apply(
+            &transaction.database(),
+            transaction,
+            delete_undeclared_indexes,
+        )
+    }
+
+    /// Deletes an object store (along with all its records) from the database
+    pub fn delete_object_store(&self, name: &str) -> Result<()> {
+        self.transaction
+            .transaction
+            .database()
+            .delete_object_store(name)
+            .map_err(Into::into)
+    }
+}
@@ -4,17 +4,21 @@
 
 extern crate wasm_bindgen_test;
 
-use std::{assert, assert_eq, option::Option};
-
-use js_sys::Array;
-use rexie::{Direction, Index, KeyPath, KeyRange, ObjectStore, Result, Rexie, TransactionMode};
+use std::{assert, assert_eq, cell::RefCell, option::Option, rc::Rc};
+
+use futures_util::{StreamExt, TryStreamExt};
+use rexie::{
+    BatchMode, DatabaseInfo, Direction, Error, ImportOptions, ImportProgress, Index, IndexSchema,
+    Key, KeyPath, KeyRange, ObjectStore, Page, Result, Rexie, RexieBuilder, StoreSchema,
+    TransactionMode,
+};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
 struct Employee {
     id: u32,
     name: String,
@@ -27,7 +31,7 @@ struct EmployeeRequest<'a> {
     email: &'a str,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
 struct Invoice {
     id: usize,
     year: u16,
@@ -130,7 +134,7 @@ async fn add_employee(rexie: &Rexie, name: &str, email: &str) -> Result<u32> {
     let employee_id = employees.add(&employee, None).await?;
 
     transaction.commit().await?;
-    Ok(num_traits::cast(employee_id.as_f64().unwrap()).unwrap())
+    employee_id.try_into()
 }
 
 async fn add_all_employees(rexie: &Rexie, iter: impl Iterator<Item = (&str, &str)>) -> Result<()> {
@@ -164,7 +168,7 @@ async fn get_employee(rexie: &Rexie, id: u32) -> Result<Option<Employee>> {
     let employees = employees.unwrap();
 
     Ok(employees
-        .get(id.into())
+        .get(id)
         .await?
         .map(|value| serde_wasm_bindgen::from_value::<Employee>(value).unwrap()))
 }
@@ -255,7 +259,7 @@ async fn get_invoice(rexie: &Rexie, id: usize, year: u16) -> Result<Option<Invoi
     let invoices = invoices.unwrap();
 
     let invoice = invoices
-        .get(Array::of2(&JsValue::from_f64(id as _), &JsValue::from_f64(year as _)).into())
+        .get((Key::try_from(id)?, year))
         .await?
         .map(|value| serde_wasm_bindgen::from_value(value).unwrap());
 
@@ -281,7 +285,7 @@ async fn get_all_invoices_by_agent_and_customer(
 
     let invoices = agent_customer_index
         .scan(
-            Some(KeyRange::only(&Array::of2(&agent.into(), &customer.into())).unwrap()),
+            Some(KeyRange::only((agent, customer)).unwrap()),
             None,
             None,
             None,
@@ -388,13 +392,13 @@ async fn test_db_count_and_clear_pass() {
     // Count the number of values in the database before and after clearing.
     assert_eq!(count_employees(&rexie, None).await, Ok(2));
     assert_eq!(
-        count_employees(&rexie, Some(KeyRange::only(&1u32.into()).unwrap())).await,
+        count_employees(&rexie, Some(KeyRange::only(1u32).unwrap())).await,
         Ok(1)
     );
     assert_eq!(
         count_employees(
             &rexie,
-            Some(KeyRange::lower_bound(&1u32.into(), Some(true)).unwrap())
+            Some(KeyRange::lower_bound(1u32, Some(true)).unwrap())
         )
         .await,
         Ok(1)
@@ -402,7 +406,7 @@ async fn test_db_count_and_clear_pass() {
     assert_eq!(
         count_employees(
             &rexie,
-            Some(KeyRange::lower_bound(&2u32.into(), Some(false)).unwrap())
+            Some(KeyRange::lower_bound(2u32, Some(false)).unwrap())
         )
         .await,
         Ok(1)
@@ -410,7 +414,7 @@ async fn test_db_count_and_clear_pass() {
     assert_eq!(
         count_employees(
             &rexie,
-            Some(KeyRange::lower_bound(&2u32.into(), Some(true)).unwrap())
+            Some(KeyRange::lower_bound(2u32, Some(true)).unwrap())
         )
         .await,
         Ok(0)
@@ -421,6 +425,969 @@ async fn test_db_count_and_clear_pass() {
     close_and_delete_db(rexie).await;
 }
 
+#[wasm_bindgen_test]
+async fn test_key_range_from_range() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+        ("Shaggy Rogers", "shaggy@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let count = |key_range| count_employees(&rexie, Some(key_range));
+
+    assert_eq!(count(KeyRange::from_range(2..=3).unwrap()).await, Ok(2));
+    assert_eq!(count(KeyRange::from_range(2..3).unwrap()).await, Ok(1));
+    assert_eq!(count(KeyRange::from_range(2..).unwrap()).await, Ok(3));
+    assert_eq!(count(KeyRange::from_range(..2).unwrap()).await, Ok(1));
+    assert_eq!(count(KeyRange::from_range(..=2).unwrap()).await, Ok(2));
+    assert_eq!(count(KeyRange::from_range(3..=3).unwrap()).await, Ok(1));
+    assert_eq!(count(KeyRange::from_range::<u32>(..).unwrap()).await, Ok(4));
+
+    let key_range =
+        KeyRange::from_range((std::ops::Bound::Excluded(1), std::ops::Bound::Included(3))).unwrap();
+    assert!(key_range.lower_open());
+    assert!(!key_range.upper_open());
+    assert_eq!(count(key_range).await, Ok(2));
+
+    let key_range = KeyRange::from_range("a".."b").unwrap();
+    assert_eq!(key_range.includes("apple"), Ok(true));
+    assert_eq!(key_range.includes("b"), Ok(false));
+
+    let key_range = KeyRange::from_range((1, "a")..=(1, "c")).unwrap();
+    assert_eq!(key_range.includes((1, "b")), Ok(true));
+    assert_eq!(key_range.includes((2, "a")), Ok(false));
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_key_range_prefix() {
+    let rexie = create_db().await;
+
+    assert!(add_invoice(&rexie, 1, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 1, 2023, "Scooby Doo", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 2, 2022, "John Doe", "Acme Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 3, 2022, "Johnny Bravo", "Acme Corp")
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["invoices"], TransactionMode::ReadOnly)
+        .unwrap();
+    let invoices = transaction.store("invoices").unwrap();
+
+    // Compound primary key `["id", "year"]`
+    let keys = invoices
+        .get_all_keys(Some(KeyRange::prefix([1]).unwrap()), None)
+        .await;
+    assert_eq!(keys, Ok(vec![Key::from((1, 2022)), Key::from((1, 2023))]));
+
+    // Compound index key `["agent", "customer"]`
+    let agent_customer = invoices.index("agent_customer").unwrap();
+    let keys = agent_customer
+        .get_all_keys(Some(KeyRange::prefix(["John Doe"]).unwrap()), None)
+        .await;
+    assert_eq!(keys, Ok(vec![Key::from((2, 2022)), Key::from((1, 2022))]));
+
+    // String prefix of the first element of the index key
+    let key_range = KeyRange::starts_with("John").unwrap();
+    assert_eq!(key_range.includes("John Doe"), Ok(true));
+    assert_eq!(key_range.includes("Johnny Bravo"), Ok(true));
+    assert_eq!(key_range.includes("Jon"), Ok(false));
+    assert_eq!(key_range.includes("Scooby Doo"), Ok(false));
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_stream() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadOnly)
+        .unwrap();
+    let employees = transaction.store("employees").unwrap();
+
+    let stream = employees.stream(None, None).unwrap();
+    let pairs: Result<Vec<(Key, JsValue)>> = stream.try_collect().await;
+    assert!(pairs.is_ok());
+    let keys: Vec<Key> = pairs.unwrap().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec![Key::from(1), Key::from(2), Key::from(3)]);
+
+    // Only the polled pairs are read
+    let stream = employees
+        .stream(
+            Some(KeyRange::from_range(2..).unwrap()),
+            Some(Direction::Prev),
+        )
+        .unwrap();
+    let mut stream = std::pin::pin!(stream);
+    let (key, value) = stream.next().await.unwrap().unwrap();
+    assert_eq!(key, Key::from(3));
+    let employee: Employee = serde_wasm_bindgen::from_value(value).unwrap();
+    assert_eq!(employee.name, "Jane Doe");
+    let (key, _) = stream.next().await.unwrap().unwrap();
+    assert_eq!(key, Key::from(2));
+    assert!(stream.next().await.is_none());
+
+    let email = employees.index("email").unwrap();
+    let emails: Vec<Key> = email
+        .stream(None, None)
+        .unwrap()
+        .map(|pair| pair.unwrap().0)
+        .collect()
+        .await;
+    assert_eq!(
+        emails,
+        vec![
+            Key::from("jane@example.com"),
+            Key::from("john@example.com"),
+            Key::from("scooby@example.com"),
+        ]
+    );
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_scan_keys() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadOnly)
+        .unwrap();
+    let employees = transaction.store("employees").unwrap();
+
+    let keys = employees.scan_keys(None, None, None, None).await;
+    assert_eq!(
+        keys,
+        Ok(vec![
+            (Key::from(1), Key::from(1)),
+            (Key::from(2), Key::from(2)),
+            (Key::from(3), Key::from(3)),
+        ])
+    );
+
+    let keys = employees
+        .scan_keys(None, Some(1), Some(1), Some(Direction::Prev))
+        .await;
+    assert_eq!(keys, Ok(vec![(Key::from(2), Key::from(2))]));
+
+    let email = employees.index("email").unwrap();
+    let keys = email
+        .scan_keys(Some(KeyRange::starts_with("j").unwrap()), None, None, None)
+        .await;
+    assert_eq!(
+        keys,
+        Ok(vec![
+            (Key::from("jane@example.com"), Key::from(3)),
+            (Key::from("john@example.com"), Key::from(1)),
+        ])
+    );
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_update_and_delete_where() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadWrite)
+        .unwrap();
+    let employees = transaction.store("employees").unwrap();
+
+    // Rename the employees named `Doe` (except the first one)
+    let updated = employees
+        .update_where(Some(KeyRange::from_range(2..).unwrap()), |_, value| {
+            let name = js_sys::Reflect::get(&value, &"name".into())
+                .ok()?
+                .as_string()?;
+            let name = name.strip_suffix(" Doe")?;
+
+            js_sys::Reflect::set(&value, &"name".into(), &format!("{name} Dee").into()).ok()?;
+            Some(value)
+        })
+        .await;
+    assert_eq!(updated, Ok(1));
+
+    let deleted = employees
+        .delete_where(None, |key, _| *key == Key::from(2))
+        .await;
+    assert_eq!(deleted, Ok(1));
+
+    assert!(transaction.done().await.is_ok());
+
+    let employees = get_all_employees(&rexie, None).await;
+    assert!(employees.is_ok());
+    let employees = employees.unwrap();
+    assert_eq!(employees.len(), 2);
+    assert_eq!(employees[0].name, "John Doe");
+    assert_eq!(employees[1].name, "Jane Dee");
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_scan_page() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+        ("Shaggy Rogers", "shaggy@example.com"),
+        ("Fred Jones", "fred@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadWrite)
+        .unwrap();
+    let employees = transaction.store("employees").unwrap();
+
+    let keys = |page: &Page| {
+        page.items
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let page = employees.scan_page(None, 2, None, None).await.unwrap();
+    assert_eq!(keys(&page), vec![Key::from(1), Key::from(2)]);
+    assert!(page.next.is_some());
+
+    // Pages don't shift when records are deleted
+    assert!(employees.delete(3).await.is_ok());
+
+    let page = employees.scan_page(None, 2, None, page.next).await.unwrap();
+    assert_eq!(keys(&page), vec![Key::from(4), Key::from(5)]);
+    assert!(page.next.is_none());
+
+    let page = employees
+        .scan_page(None, 3, Some(Direction::Prev), None)
+        .await
+        .unwrap();
+    assert_eq!(keys(&page), vec![Key::from(5), Key::from(4), Key::from(2)]);
+
+    let page = employees
+        .scan_page(None, 3, Some(Direction::Prev), page.next)
+        .await
+        .unwrap();
+    assert_eq!(keys(&page), vec![Key::from(1)]);
+    assert!(page.next.is_none());
+
+    assert!(transaction.done().await.is_ok());
+
+    // Duplicate index keys are paginated by their primary keys
+    assert!(add_invoice(&rexie, 1, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 2, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 3, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["invoices"], TransactionMode::ReadOnly)
+        .unwrap();
+    let agent_customer = transaction
+        .store("invoices")
+        .unwrap()
+        .index("agent_customer")
+        .unwrap();
+
+    let mut ids = Vec::new();
+    let mut token = None;
+
+    loop {
+        let page = agent_customer
+            .scan_page(None, 2, None, token)
+            .await
+            .unwrap();
+
+        for (_, value) in page.items {
+            let invoice: Invoice = serde_wasm_bindgen::from_value(value).unwrap();
+            ids.push(invoice.id);
+        }
+
+        token = page.next;
+
+        if token.is_none() {
+            break;
+        }
+    }
+
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_distinct_keys() {
+    let rexie = create_db().await;
+
+    assert!(add_invoice(&rexie, 1, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 2, 2022, "Scooby Doo", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 3, 2022, "John Doe", "Umbrella Corp")
+        .await
+        .is_ok());
+    assert!(add_invoice(&rexie, 4, 2022, "John Doe", "Acme Corp")
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["invoices"], TransactionMode::ReadOnly)
+        .unwrap();
+    let agent_customer = transaction
+        .store("invoices")
+        .unwrap()
+        .index("agent_customer")
+        .unwrap();
+
+    assert_eq!(
+        agent_customer.distinct_keys(None, None, false).await,
+        Ok(vec![
+            Key::from(("John Doe", "Acme Corp")),
+            Key::from(("John Doe", "Umbrella Corp")),
+            Key::from(("Scooby Doo", "Umbrella Corp")),
+        ])
+    );
+    assert_eq!(
+        agent_customer
+            .distinct_keys(
+                Some(KeyRange::prefix(["John Doe"]).unwrap()),
+                Some(1),
+                false
+            )
+            .await,
+        Ok(vec![Key::from(("John Doe", "Acme Corp"))])
+    );
+    assert_eq!(
+        agent_customer.distinct_keys(None, None, true).await,
+        Ok(vec![
+            Key::from(("Scooby Doo", "Umbrella Corp")),
+            Key::from(("John Doe", "Umbrella Corp")),
+            Key::from(("John Doe", "Acme Corp")),
+        ])
+    );
+    assert_eq!(
+        agent_customer
+            .distinct_keys(Some(KeyRange::prefix(["John Doe"]).unwrap()), Some(1), true)
+            .await,
+        Ok(vec![Key::from(("John Doe", "Umbrella Corp"))])
+    );
+    assert_eq!(agent_customer.count_distinct(None).await, Ok(3));
+    assert_eq!(agent_customer.count(None).await, Ok(4));
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+fn employee_requests<'a>(
+    employees: &'a [(&'a str, &'a str)],
+) -> impl Iterator<Item = (JsValue, Option<Key>)> + 'a {
+    employees.iter().map(|(name, email)| {
+        let request = EmployeeRequest { name, email };
+        (serde_wasm_bindgen::to_value(&request).unwrap(), None)
+    })
+}
+
+#[wasm_bindgen_test]
+async fn test_try_add_all() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("John Doe Jr.", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+    ];
+
+    // Failed items are skipped
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadWrite)
+        .unwrap();
+    let store = transaction.store("employees").unwrap();
+
+    let results = store
+        .try_add_all(employee_requests(&employees), BatchMode::Skip)
+        .await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], Ok(Key::from(1)));
+    assert!(results[1].is_err());
+    assert!(results[2].is_ok());
+
+    assert!(transaction.done().await.is_ok());
+    assert_eq!(count_employees(&rexie, None).await, Ok(2));
+    assert!(clear_employees(&rexie).await.is_ok());
+
+    // First failed item aborts the whole batch
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadWrite)
+        .unwrap();
+    let store = transaction.store("employees").unwrap();
+
+    let results = store
+        .try_put_all(employee_requests(&employees), BatchMode::Abort)
+        .await;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_err));
+
+    let _ = transaction.done().await;
+    assert_eq!(count_employees(&rexie, None).await, Ok(0));
+
+    // Synchronously failed item (`NaN` is not a valid key) aborts the batch as well
+    let departments = vec![
+        (JsValue::from_str("Sales"), None),
+        (JsValue::from_str("Marketing"), Some(Key::Number(f64::NAN))),
+        (JsValue::from_str("Finance"), None),
+        (JsValue::from_str("Legal"), None),
+    ];
+
+    let transaction = rexie
+        .transaction(&["departments"], TransactionMode::ReadWrite)
+        .unwrap();
+    let store = transaction.store("departments").unwrap();
+
+    let results = store
+        .try_add_all(departments.into_iter(), BatchMode::Abort)
+        .await;
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(Result::is_err));
+
+    let _ = transaction.done().await;
+
+    let transaction = rexie
+        .transaction(&["departments"], TransactionMode::ReadOnly)
+        .unwrap();
+    let store = transaction.store("departments").unwrap();
+    assert_eq!(store.count(None).await, Ok(0));
+    assert!(transaction.done().await.is_ok());
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_delete_range_and_matching() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+        ("Shaggy Rogers", "shaggy@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadWrite)
+        .unwrap();
+    let store = transaction.store("employees").unwrap();
+
+    let deleted = store.delete_range(KeyRange::from_range(3..).unwrap()).await;
+    assert_eq!(deleted, Ok(2));
+
+    let email = store.index("email").unwrap();
+    let deleted = email
+        .delete_matching(KeyRange::starts_with("j").unwrap())
+        .await;
+    assert_eq!(deleted, Ok(1));
+
+    assert_eq!(store.get_all_keys(None, None).await, Ok(vec![Key::from(2)]));
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_get_many() {
+    let rexie = create_db().await;
+
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Jane Doe", "jane@example.com"),
+    ];
+    assert!(add_all_employees(&rexie, employees.into_iter())
+        .await
+        .is_ok());
+
+    let transaction = rexie
+        .transaction(&["employees"], TransactionMode::ReadOnly)
+        .unwrap();
+    let store = transaction.store("employees").unwrap();
+
+    let names = |values: Vec<Option<JsValue>>| {
+        values
+            .into_iter()
+            .map(|value| {
+                value.map(|value| {
+                    serde_wasm_bindgen::from_value::<Employee>(value)
+                        .unwrap()
+                        .name
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let values = store.get_many([3, 5, 1]).await;
+    assert!(values.is_ok());
+    assert_eq!(
+        names(values.unwrap()),
+        vec![
+            Some("Jane Doe".to_owned()),
+            None,
+            Some("John Doe".to_owned())
+        ]
+    );
+
+    let email = store.index("email").unwrap();
+    let values = email
+        .get_many(["scooby@example.com", "shaggy@example.com"])
+        .await;
+    assert!(values.is_ok());
+    assert_eq!(
+        names(values.unwrap()),
+        vec![Some("Scooby Doo".to_owned()), None]
+    );
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_bulk_import() {
+    let rexie = create_db().await;
+
+    let progress = Rc::new(RefCell::new(Vec::new()));
+    let options = || {
+        let progress = progress.clone();
+        ImportOptions::new()
+            .chunk_size(2)
+            .on_progress(move |p| progress.borrow_mut().push(p))
+    };
+
+    // Second chunk fails because of the unique email index
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Shaggy Rogers", "shaggy@example.com"),
+        ("John Doe Jr.", "john@example.com"),
+        ("Scrappy Doo", "scrappy@example.com"),
+    ];
+    let records = futures_util::stream::iter(employee_requests(&employees));
+    let result = rexie.bulk_import("employees", records, options()).await;
+    assert!(matches!(
+        result,
+        Err(Error::ImportFailed { committed: 2, .. })
+    ));
+    assert_eq!(
+        *progress.borrow(),
+        vec![ImportProgress {
+            chunks: 1,
+            committed: 2
+        }]
+    );
+    assert_eq!(count_employees(&rexie, None).await, Ok(2));
+
+    // Resume after fixing the input
+    progress.borrow_mut().clear();
+    let employees = [
+        ("John Doe", "john@example.com"),
+        ("Scooby Doo", "scooby@example.com"),
+        ("Shaggy Rogers", "shaggy@example.com"),
+        ("John Doe Jr.", "johnjr@example.com"),
+        ("Scrappy Doo", "scrappy@example.com"),
+    ];
+    let records = futures_util::stream::iter(employee_requests(&employees));
+    let result = rexie
+        .bulk_import("employees", records, options().resume_from(2))
+        .await;
+    assert_eq!(result, Ok(5));
+    assert_eq!(
+        *progress.borrow(),
+        vec![
+            ImportProgress {
+                chunks: 1,
+                committed: 4
+            },
+            ImportProgress {
+                chunks: 2,
+                committed: 5
+            }
+        ]
+    );
+    assert_eq!(count_employees(&rexie, None).await, Ok(5));
+
+    // Records of a chunk with an invalid record are not committed (`NaN` is not a valid key)
+    let departments = |invalid_key: Option<Key>| {
+        futures_util::stream::iter(vec![
+            (JsValue::from_str("Sales"), None),
+            (JsValue::from_str("Marketing"), invalid_key),
+            (JsValue::from_str("Finance"), None),
+        ])
+    };
+    let count_departments = || async {
+        rexie
+            .read(&["departments"], |transaction| async move {
+                transaction.store("departments")?.count(None).await
+            })
+            .await
+    };
+
+    let result = rexie
+        .bulk_import(
+            "departments",
+            departments(Some(Key::Number(f64::NAN))),
+            ImportOptions::new().chunk_size(3),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::ImportFailed { committed: 0, .. })
+    ));
+    assert_eq!(count_departments().await, Ok(0));
+
+    // Resuming doesn't duplicate records
+    let result = rexie
+        .bulk_import(
+            "departments",
+            departments(None),
+            ImportOptions::new().chunk_size(3).resume_from(0),
+        )
+        .await;
+    assert_eq!(result, Ok(3));
+    assert_eq!(count_departments().await, Ok(3));
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_store_update() {
+    let rexie = create_db().await;
+
+    let id = add_employee(&rexie, "John Doe", "john@example.com").await;
+    assert_eq!(id, Ok(1));
+
+    let transaction = rexie
+        .transaction(&["employees", "departments"], TransactionMode::ReadWrite)
+        .unwrap();
+
+    // Store with inline keys
+    let employees = transaction.store("employees").unwrap();
+    let result = employees
+        .update(1, |old| {
+            let mut employee: Employee = serde_wasm_bindgen::from_value(old?).unwrap();
+            employee.name = "John Doe Jr.".to_owned();
+            Some(serde_wasm_bindgen::to_value(&employee).unwrap())
+        })
+        .await;
+    assert!(result.is_ok());
+    let (old, new) = result.unwrap();
+    assert_eq!(
+        serde_wasm_bindgen::from_value::<Employee>(old.unwrap())
+            .unwrap()
+            .name,
+        "John Doe"
+    );
+    assert_eq!(
+        serde_wasm_bindgen::from_value::<Employee>(new.unwrap())
+            .unwrap()
+            .name,
+        "John Doe Jr."
+    );
+
+    // New value must keep the key of the record
+    let result = employees
+        .update(1, |old| {
+            let mut employee: Employee = serde_wasm_bindgen::from_value(old?).unwrap();
+            employee.id = 2;
+            Some(serde_wasm_bindgen::to_value(&employee).unwrap())
+        })
+        .await;
+    assert_eq!(result, Err(Error::KeyChanged(Key::Number(1.0))));
+    let result = employees
+        .update(1, |_| Some(JsValue::from(js_sys::Object::new())))
+        .await;
+    assert_eq!(result, Err(Error::KeyChanged(Key::Number(1.0))));
+    assert_eq!(employees.count(None).await, Ok(1));
+
+    // Returning `None` deletes the record
+    let result = employees.update(1, |_| None).await;
+    assert!(matches!(result, Ok((Some(_), None))));
+    assert_eq!(employees.count(None).await, Ok(0));
+
+    // Store with out-of-line keys
+    let departments = transaction.store("departments").unwrap();
+    let increment = |old: Option<JsValue>| {
+        let count = old.and_then(|old| old.as_f64()).unwrap_or_default();
+        Some(JsValue::from_f64(count + 1.0))
+    };
+    assert!(departments.update("visits", increment).await.is_ok());
+    let result = departments.update("visits", increment).await;
+    assert!(result.is_ok());
+    let (old, new) = result.unwrap();
+    assert_eq!(old.and_then(|old| old.as_f64()), Some(1.0));
+    assert_eq!(new.and_then(|new| new.as_f64()), Some(2.0));
+
+    assert!(transaction.done().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_put_if_version() {
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Document {
+        id: u32,
+        text: String,
+        #[serde(default)]
+        version: u32,
+    }
+
+    let document = |text: &str| {
+        serde_wasm_bindgen::to_value(&Document {
+            id: 1,
+            text: text.to_owned(),
+            version: 0,
+        })
+        .unwrap()
+    };
+
+    assert!(Rexie::delete("test").await.is_ok());
+    let rexie = Rexie::builder("test")
+        .version(1)
+        .add_object_store(
+            ObjectStore::new("documents")
+                .key_path("id")
+                .version_attribute("version"),
+        )
+        .add_object_store(ObjectStore::new("notes"))
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+
+    let transaction = rexie
+        .transaction(&["documents", "notes"], TransactionMode::ReadWrite)
+        .unwrap();
+    let documents = transaction.store("documents").unwrap();
+
+    // Missing values have version `0`
+    assert_eq!(
+        documents.put_if_version(&document("first"), None, 0).await,
+        Ok(1)
+    );
+    assert_eq!(
+        documents.put_if_version(&document("second"), None, 1).await,
+        Ok(2)
+    );
+
+    // Stale version is rejected
+    assert_eq!(
+        documents.put_if_version(&document("stale"), None, 1).await,
+        Err(Error::VersionConflict {
+            expected: 1,
+            found: 2
+        })
+    );
+
+    let stored = documents.get(1).await.unwrap().unwrap();
+    assert_eq!(
+        serde_wasm_bindgen::from_value::<Document>(stored).unwrap(),
+        Document {
+            id: 1,
+            text: "second".to_owned(),
+            version: 2,
+        }
+    );
+
+    // Values must be objects and versions can't overflow
+    assert!(matches!(
+        documents
+            .put_if_version(&JsValue::from_str("text"), None, 2)
+            .await,
+        Err(Error::NotAnObject(_))
+    ));
+    assert_eq!(
+        documents
+            .put_if_version(&document("last"), None, u32::MAX)
+            .await,
+        Err(Error::VersionOverflow(u32::MAX))
+    );
+
+    // Stores without version attribute don't support optimistic concurrency
+    let notes = transaction.store("notes").unwrap();
+    assert_eq!(
+        notes
+            .put_if_version(&document("note"), Some(1.into()), 0)
+            .await,
+        Err(Error::VersionAttributeMissing("notes".to_owned()))
+    );
+
+    assert!(transaction.done().await.is_ok());
+    rexie.close();
+
+    let documents_store = || {
+        ObjectStore::new("documents")
+            .key_path("id")
+            .version_attribute("version")
+    };
+
+    // Version attributes are available in upgrades
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(documents_store())
+        .add_object_store(ObjectStore::new("notes"))
+        .on_upgrade(move |upgrade| async move {
+            let documents = upgrade.transaction().store("documents")?;
+            let version = documents
+                .put_if_version(&document("upgraded"), None, 2)
+                .await?;
+            assert_eq!(version, 3);
+            Ok(())
+        })
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    rexie.unwrap().close();
+
+    // ... and in existing databases opened with the declared object stores
+    let rexie = Rexie::builder("test")
+        .add_object_store(documents_store())
+        .open_existing()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+
+    let transaction = rexie
+        .transaction(&["documents"], TransactionMode::ReadWrite)
+        .unwrap();
+    let documents = transaction.store("documents").unwrap();
+    assert_eq!(
+        documents.put_if_version(&document("third"), None, 3).await,
+        Ok(4)
+    );
+    assert!(transaction.done().await.is_ok());
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_scoped_transactions() {
+    #[derive(Debug, PartialEq)]
+    enum AppError {
+        Rexie(Error),
+        Invalid,
+    }
+
+    impl From<Error> for AppError {
+        fn from(err: Error) -> Self {
+            AppError::Rexie(err)
+        }
+    }
+
+    let rexie = create_db().await;
+
+    let employee = |name: &str, email: &str| {
+        serde_wasm_bindgen::to_value(&EmployeeRequest { name, email }).unwrap()
+    };
+
+    // Writes are committed when the closure returns `Ok`
+    let john = employee("John Doe", "john@example.com");
+    let result = rexie
+        .write(&["employees"], |transaction| async move {
+            let employees = transaction.store("employees")?;
+            employees.add(&john, None).await
+        })
+        .await;
+    assert_eq!(result, Ok(Key::Number(1.0)));
+
+    // Writes are rolled back when the closure returns `Err`
+    let scooby = employee("Scooby Doo", "scooby@example.com");
+    let result: std::result::Result<(), AppError> = rexie
+        .write(&["employees"], |transaction| async move {
+            let employees = transaction.store("employees")?;
+            employees.add(&scooby, None).await?;
+            Err(AppError::Invalid)
+        })
+        .await;
+    assert_eq!(result, Err(AppError::Invalid));
+
+    // The closure can commit (or wait for) the transaction on its own
+    let scooby = employee("Scooby Doo", "scooby@example.com");
+    let result = rexie
+        .write(&["employees"], |transaction| async move {
+            let key = transaction.store("employees")?.add(&scooby, None).await?;
+            transaction.commit().await?;
+            Ok::<_, Error>(key)
+        })
+        .await;
+    assert!(result.is_ok());
+
+    let count = rexie
+        .read(&["employees"], |transaction| async move {
+            let count = transaction.store("employees")?.count(None).await?;
+            transaction.done().await?;
+            Ok::<_, Error>(count)
+        })
+        .await;
+    assert_eq!(count, Ok(2));
+
+    close_and_delete_db(rexie).await;
+}
+
 #[wasm_bindgen_test]
 async fn test_get_all_pass() {
     let rexie = create_db().await;
@@ -543,3 +1510,672 @@ async fn test_db_reopen() {
 
     close_and_delete_db(rexie).await;
 }
+
+#[wasm_bindgen_test]
+async fn test_db_upgrade_callback() {
+    let rexie = create_db().await;
+    let id = add_employee(&rexie, "John Doe", "john@example.com").await;
+    assert_eq!(id, Ok(1));
+    rexie.close();
+
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(
+            ObjectStore::new("employees")
+                .key_path("id")
+                .auto_increment(true)
+                .add_index(Index::new("email", "email").unique(true)),
+        )
+        .add_object_store(ObjectStore::new("departments").auto_increment(true))
+        .add_object_store(
+            ObjectStore::new("invoices")
+                .key_path_array(["id", "year"])
+                .add_index(Index::new_array("agent_customer", ["agent", "customer"])),
+        )
+        .on_upgrade(|upgrade| async move {
+            assert_eq!(upgrade.old_version(), 1);
+            assert_eq!(upgrade.new_version(), 2);
+
+            let transaction = upgrade.transaction();
+            assert_eq!(transaction.mode(), Ok(TransactionMode::VersionChange));
+
+            let employees = transaction.store("employees")?;
+            let employee = employees.get(1u32).await?.unwrap();
+            js_sys::Reflect::set(&employee, &"name".into(), &"John Doe Jr.".into()).unwrap();
+            employees.put(&employee, None).await?;
+
+            Ok(())
+        })
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    basic_test_db_with_version(&rexie, 2).await;
+
+    let employee = get_employee(&rexie, 1).await.unwrap().unwrap();
+    assert_eq!(employee.name, "John Doe Jr.");
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_upgrade_callback_error() {
+    let rexie = create_db().await;
+    rexie.close();
+
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(ObjectStore::new("employees"))
+        .on_upgrade(|_| async move { Err(Error::TransactionAbortFailed) })
+        .build()
+        .await;
+    assert_eq!(rexie.err(), Some(Error::TransactionAbortFailed));
+
+    let rexie = create_db().await;
+    basic_test_db(&rexie).await;
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_delete_object_store_and_index() {
+    let rexie = create_db().await;
+    rexie.close();
+
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(
+            ObjectStore::new("employees")
+                .key_path("id")
+                .auto_increment(true)
+                .delete_index("email"),
+        )
+        .delete_object_store("departments")
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+
+    // Object stores which are not declared are deleted as well.
+    assert_eq!(rexie.store_names(), vec!["employees"]);
+
+    let transaction = rexie.transaction(&["employees"], TransactionMode::ReadOnly);
+    assert!(transaction.is_ok());
+    let transaction = transaction.unwrap();
+
+    let employees = transaction.store("employees");
+    assert!(employees.is_ok());
+    let employees = employees.unwrap();
+    assert!(employees.index_names().is_empty());
+
+    assert!(transaction.done().await.is_ok());
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_delete_undeclared() {
+    let rexie = create_db().await;
+    rexie.close();
+
+    // Object stores and indexes which are not declared are deleted implicitly
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(
+            ObjectStore::new("employees")
+                .key_path("id")
+                .auto_increment(true),
+        )
+        .add_object_store(ObjectStore::new("departments").auto_increment(true))
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert_eq!(rexie.store_names(), vec!["departments", "employees"]);
+
+    let schema = rexie.schema().unwrap();
+    assert!(schema.store("employees").unwrap().indexes.is_empty());
+
+    close_and_delete_db(rexie).await;
+}
+
+fn migration_db(migrations_run: &Rc<RefCell<Vec<u32>>>, latest_version: u32) -> RexieBuilder {
+    let mut builder = Rexie::builder("test");
+
+    for version in 1..=latest_version {
+        let migrations_run = migrations_run.clone();
+
+        builder = builder.migration(version, move |upgrade| async move {
+            migrations_run.borrow_mut().push(version);
+
+            match version {
+                1 => upgrade.add_object_store(
+                    ObjectStore::new("employees")
+                        .key_path("id")
+                        .auto_increment(true),
+                ),
+                2 => {
+                    upgrade.add_object_store(
+                        ObjectStore::new("employees")
+                            .add_index(Index::new("email", "email").unique(true)),
+                    )?;
+                    upgrade.add_object_store(ObjectStore::new("departments"))
+                }
+                _ => {
+                    let employees = upgrade.transaction().store("employees")?;
+
+                    for (_, value) in employees.scan(None, None, None, None).await? {
+                        js_sys::Reflect::set(&value, &"name".into(), &"Migrated".into()).unwrap();
+                        employees.put(&value, None).await?;
+                    }
+
+                    upgrade.delete_object_store("departments")
+                }
+            }
+        });
+    }
+
+    builder
+}
+
+#[wasm_bindgen_test]
+async fn test_db_migrations() {
+    assert!(Rexie::delete("test").await.is_ok());
+
+    let migrations_run = Rc::new(RefCell::new(Vec::new()));
+
+    let rexie = migration_db(&migrations_run, 1).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert_eq!(rexie.version(), Ok(1));
+    assert_eq!(rexie.store_names(), vec!["employees"]);
+    assert_eq!(*migrations_run.borrow(), vec![1]);
+
+    let id = add_employee(&rexie, "John Doe", "john@example.com").await;
+    assert_eq!(id, Ok(1));
+    rexie.close();
+
+    migrations_run.borrow_mut().clear();
+
+    let rexie = migration_db(&migrations_run, 3).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert_eq!(rexie.version(), Ok(3));
+    assert_eq!(rexie.store_names(), vec!["employees"]);
+    assert_eq!(*migrations_run.borrow(), vec![2, 3]);
+
+    let employee = get_employee(&rexie, 1).await.unwrap().unwrap();
+    assert_eq!(employee.name, "Migrated");
+
+    // The unique index added by the second migration is in place.
+    let id = add_employee(&rexie, "John Doe New", "john@example.com").await;
+    assert!(id.is_err());
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_schema() {
+    let rexie = create_db().await;
+
+    let schema = rexie.schema();
+    assert!(schema.is_ok());
+    let schema = schema.unwrap();
+
+    assert_eq!(schema.name, "test");
+    assert_eq!(schema.version, 1);
+    assert_eq!(
+        schema
+            .stores
+            .iter()
+            .map(|store| store.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["departments", "employees", "invoices"]
+    );
+
+    let departments = schema.store("departments").unwrap();
+    assert_eq!(departments.key_path, None);
+    assert!(departments.auto_increment);
+    assert!(departments.indexes.is_empty());
+
+    let employees = schema.store("employees").unwrap();
+    assert_eq!(employees.key_path, Some(KeyPath::new_single("id")));
+    assert!(employees.auto_increment);
+    assert_eq!(
+        employees.indexes,
+        vec![IndexSchema {
+            name: "email".to_owned(),
+            key_path: KeyPath::new_single("email"),
+            unique: true,
+            multi_entry: false,
+        }]
+    );
+
+    let invoices = schema.store("invoices").unwrap();
+    assert_eq!(invoices.key_path, Some(KeyPath::new_array(["id", "year"])));
+    assert!(!invoices.auto_increment);
+    assert_eq!(
+        invoices.index("agent_customer").unwrap().key_path,
+        KeyPath::new_array(["agent", "customer"])
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["stores"][1]["key_path"], "id");
+        assert_eq!(json["stores"][2]["key_path"][1], "year");
+        assert_eq!(
+            serde_json::from_value::<rexie::Schema>(json).unwrap(),
+            schema
+        );
+    }
+
+    close_and_delete_db(rexie).await;
+}
+
+fn auto_upgrade_db(with_invoices: bool) -> RexieBuilder {
+    let builder = Rexie::builder("test")
+        .auto_upgrade(true)
+        .add_object_store(
+            ObjectStore::new("employees")
+                .key_path("id")
+                .auto_increment(true)
+                .add_index(Index::new("email", "email").unique(true)),
+        )
+        .add_object_store(ObjectStore::new("departments").auto_increment(true));
+
+    if with_invoices {
+        builder.add_object_store(
+            ObjectStore::new("invoices")
+                .key_path_array(["id", "year"])
+                .add_index(Index::new_array("agent_customer", ["agent", "customer"])),
+        )
+    } else {
+        builder
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_db_auto_upgrade() {
+    assert!(Rexie::delete("test").await.is_ok());
+
+    let rexie = auto_upgrade_db(true).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    basic_test_db(&rexie).await;
+    rexie.close();
+
+    // Same schema doesn't bump the version
+    let rexie = auto_upgrade_db(true).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    basic_test_db(&rexie).await;
+
+    let schema = rexie.schema().unwrap();
+    let declared: Vec<StoreSchema> = [
+        ObjectStore::new("employees")
+            .key_path("id")
+            .auto_increment(true),
+        ObjectStore::new("departments"),
+    ]
+    .iter()
+    .map(Into::into)
+    .collect();
+
+    let diff = schema.diff(&declared);
+    assert_eq!(diff.added_stores, Vec::<String>::new());
+    assert_eq!(diff.removed_stores, vec!["invoices"]);
+    assert_eq!(diff.changed_stores, vec!["departments"]);
+    assert_eq!(
+        diff.removed_indexes,
+        vec![("employees".to_owned(), "email".to_owned())]
+    );
+    assert!(diff.added_indexes.is_empty());
+    assert!(diff.changed_indexes.is_empty());
+    rexie.close();
+
+    // Removing an object store bumps the version
+    let rexie = auto_upgrade_db(false).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert_eq!(rexie.version(), Ok(2));
+    assert_eq!(rexie.store_names(), vec!["departments", "employees"]);
+    rexie.close();
+
+    // Declared version is checked against the schema changes
+    let rexie = auto_upgrade_db(true).version(2).build().await;
+    assert_eq!(rexie.err(), Some(Error::VersionNotBumped(2, 2)));
+
+    let rexie = auto_upgrade_db(true).version(5).build().await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    basic_test_db_with_version(&rexie, 5).await;
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_version_change() {
+    assert!(Rexie::delete("test").await.is_ok());
+
+    let version_changes = Rc::new(RefCell::new(Vec::new()));
+    let version_changes_clone = version_changes.clone();
+
+    let rexie = Rexie::builder("test")
+        .version(1)
+        .add_object_store(ObjectStore::new("employees"))
+        .on_version_change(move |old_version, new_version| {
+            version_changes_clone
+                .borrow_mut()
+                .push((old_version, new_version));
+        })
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert!(!rexie.is_version_changed());
+
+    // Upgrading from another connection closes the first one instead of being blocked by it
+    let upgraded = Rexie::builder("test")
+        .version(2)
+        .add_object_store(ObjectStore::new("employees"))
+        .build()
+        .await;
+    assert!(upgraded.is_ok());
+    let upgraded = upgraded.unwrap();
+
+    assert_eq!(*version_changes.borrow(), vec![(1, Some(2))]);
+    assert!(rexie.is_version_changed());
+    assert_eq!(
+        rexie
+            .transaction(&["employees"], TransactionMode::ReadOnly)
+            .err(),
+        Some(Error::VersionChanged)
+    );
+    assert_eq!(rexie.schema().err(), Some(Error::VersionChanged));
+
+    close_and_delete_db(upgraded).await;
+}
+
+/// Opens a connection which doesn't close when another connection wants to upgrade the database
+async fn open_blocking_connection() -> idb::Database {
+    let factory = idb::Factory::new().unwrap();
+    let database = factory.open("test", Some(1)).unwrap().await;
+    assert!(database.is_ok());
+    database.unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn test_db_blocked() {
+    assert!(Rexie::delete("test").await.is_ok());
+    let blocking = open_blocking_connection().await;
+
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(ObjectStore::new("employees"))
+        .build()
+        .await;
+    assert_eq!(rexie.err(), Some(Error::Blocked));
+
+    // The cancelled upgrade doesn't run once the blocking connection is closed
+    blocking.close();
+
+    let rexie = Rexie::open_existing("test").await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    assert_eq!(rexie.version(), Ok(1));
+    assert!(rexie.store_names().is_empty());
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_on_blocked() {
+    assert!(Rexie::delete("test").await.is_ok());
+    let blocking = Rc::new(RefCell::new(Some(open_blocking_connection().await)));
+
+    let blocked = Rc::new(RefCell::new(Vec::new()));
+    let blocked_clone = blocked.clone();
+    let blocking_clone = blocking.clone();
+
+    // `build` waits until the blocking connection is closed
+    let rexie = Rexie::builder("test")
+        .version(2)
+        .add_object_store(ObjectStore::new("employees"))
+        .on_blocked(move |old_version, new_version| {
+            blocked_clone.borrow_mut().push((old_version, new_version));
+
+            if let Some(blocking) = blocking_clone.borrow_mut().take() {
+                blocking.close();
+            }
+        })
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+
+    assert_eq!(*blocked.borrow(), vec![(1, 2)]);
+    assert!(blocking.borrow().is_none());
+    assert_eq!(rexie.version(), Ok(2));
+    assert_eq!(rexie.store_names(), vec!["employees"]);
+
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_db_databases() {
+    let rexie = create_db_with_version(3).await;
+
+    let databases = Rexie::databases().await;
+    assert!(databases.is_ok());
+    assert!(databases.unwrap().contains(&DatabaseInfo {
+        name: "test".to_owned(),
+        version: 3,
+    }));
+    assert_eq!(Rexie::exists("test").await, Ok(true));
+
+    close_and_delete_db(rexie).await;
+
+    assert_eq!(Rexie::exists("test").await, Ok(false));
+}
+
+#[wasm_bindgen_test]
+async fn test_db_open_existing() {
+    assert!(Rexie::delete("test").await.is_ok());
+
+    let rexie = Rexie::open_existing("test").await;
+    assert_eq!(
+        rexie.err(),
+        Some(Error::DatabaseNotFound("test".to_owned()))
+    );
+    assert_eq!(Rexie::exists("test").await, Ok(false));
+
+    let rexie = create_db_with_version(2).await;
+    rexie.close();
+
+    let rexie = Rexie::open_existing("test").await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+    basic_test_db_with_version(&rexie, 2).await;
+
+    close_and_delete_db(rexie).await;
+}
+
+#[cfg(feature = "serde")]
+#[wasm_bindgen_test]
+async fn test_typed_store() {
+    let rexie = create_db().await;
+
+    let transaction = rexie.transaction(&["employees"], TransactionMode::ReadWrite);
+    assert!(transaction.is_ok());
+    let transaction = transaction.unwrap();
+
+    let employees = transaction.typed_store::<Employee>("employees");
+    assert!(employees.is_ok());
+    let employees = employees.unwrap();
+
+    let employee = Employee {
+        id: 1,
+        name: "John Doe".to_owned(),
+        email: "john@example.com".to_owned(),
+    };
+    assert_eq!(employees.add(&employee, None).await, Ok(Key::Number(1.0)));
+    assert_eq!(employees.get(1).await, Ok(Some(employee)));
+
+    let employee = Employee {
+        id: 1,
+        name: "Scooby Doo".to_owned(),
+        email: "scooby@example.com".to_owned(),
+    };
+    assert!(employees.put(&employee, None).await.is_ok());
+    assert_eq!(employees.get_all(None, None).await, Ok(vec![employee]));
+    assert_eq!(employees.get(2).await, Ok(None));
+
+    // Missing auto incremented keys are generated by the store
+    #[derive(Serialize, Deserialize)]
+    struct NewEmployee {
+        id: Option<u32>,
+        name: String,
+        email: String,
+    }
+
+    let new_employees = transaction.typed_store::<NewEmployee>("employees").unwrap();
+    let employee = NewEmployee {
+        id: None,
+        name: "Shaggy Rogers".to_owned(),
+        email: "shaggy@example.com".to_owned(),
+    };
+    assert_eq!(
+        new_employees.add(&employee, None).await,
+        Ok(Key::Number(2.0))
+    );
+    assert!(employees.delete(2).await.is_ok());
+
+    // Values which don't match the type of the store can't be decoded
+    let invoices = transaction.typed_store::<Invoice>("employees").unwrap();
+    assert!(matches!(
+        invoices.scan(None, None, None, None).await,
+        Err(Error::DeserializationFailed(_))
+    ));
+
+    assert!(transaction.commit().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[cfg(all(feature = "derive", feature = "serde"))]
+#[wasm_bindgen_test]
+async fn test_derive_record() {
+    use rexie::Record;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Record)]
+    struct Book {
+        #[rexie(key)]
+        isbn: String,
+        #[rexie(index = "by_author")]
+        author: String,
+        #[rexie(index, multi_entry)]
+        tags: Vec<String>,
+    }
+
+    assert_eq!(Book::STORE_NAME, "book");
+
+    assert!(Rexie::delete("test").await.is_ok());
+    let rexie = Rexie::builder("test")
+        .version(1)
+        .add_object_store(Book::object_store())
+        .build()
+        .await;
+    assert!(rexie.is_ok());
+    let rexie = rexie.unwrap();
+
+    let schema = rexie.schema().unwrap();
+    let books = schema.store("book").unwrap();
+    assert_eq!(books.key_path, Some(KeyPath::new_single("isbn")));
+    assert!(!books.auto_increment);
+    assert_eq!(
+        books.indexes,
+        vec![
+            IndexSchema {
+                name: "by_author".to_owned(),
+                key_path: KeyPath::new_single("author"),
+                unique: false,
+                multi_entry: false,
+            },
+            IndexSchema {
+                name: "tags".to_owned(),
+                key_path: KeyPath::new_single("tags"),
+                unique: false,
+                multi_entry: true,
+            },
+        ]
+    );
+
+    let transaction = rexie
+        .transaction(&[Book::STORE_NAME], TransactionMode::ReadWrite)
+        .unwrap();
+    let books = transaction.record_store::<Book>().unwrap();
+
+    let book = Book {
+        isbn: "978-0-00-000000-0".to_owned(),
+        author: "John Doe".to_owned(),
+        tags: vec!["fiction".to_owned(), "mystery".to_owned()],
+    };
+    assert!(books.add(&book, None).await.is_ok());
+    assert_eq!(books.get("978-0-00-000000-0").await, Ok(Some(book)));
+
+    let by_tag = books.store().index("tags").unwrap();
+    assert_eq!(
+        by_tag.count(Some(KeyRange::only("mystery").unwrap())).await,
+        Ok(1)
+    );
+
+    assert!(transaction.commit().await.is_ok());
+    close_and_delete_db(rexie).await;
+}
+
+#[wasm_bindgen_test]
+fn test_key_conversion() {
+    let keys = [
+        Key::from(42u32),
+        Key::from("employee"),
+        Key::Date(1_700_000_000_000.0),
+        Key::from(vec![1u8, 2, 3]),
+        Key::from((1u32, "2024", vec![4u8])),
+    ];
+
+    for key in keys {
+        assert_eq!(Key::try_from(JsValue::from(&key)), Ok(key));
+    }
+
+    assert_eq!(u32::try_from(Key::from(42u32)), Ok(42));
+    assert_eq!(
+        u8::try_from(Key::from(256u32)),
+        Err(Error::KeyTypeMismatch(Key::Number(256.0)))
+    );
+    assert_eq!(
+        String::try_from(Key::from(1.5)),
+        Err(Error::KeyTypeMismatch(Key::Number(1.5)))
+    );
+
+    // Integers outside `±Number.MAX_SAFE_INTEGER` are rejected in both directions
+    const MAX_SAFE_INTEGER: i64 = 9_007_199_254_740_991;
+    assert_eq!(
+        Key::try_from(MAX_SAFE_INTEGER),
+        Ok(Key::Number(9_007_199_254_740_991.0))
+    );
+    assert_eq!(
+        Key::try_from(MAX_SAFE_INTEGER + 1),
+        Err(Error::UnsafeInteger("9007199254740992".to_owned()))
+    );
+    assert!(Key::try_from(u64::MAX).is_err());
+    assert!(Key::try_from(-MAX_SAFE_INTEGER - 1).is_err());
+    assert_eq!(
+        i64::try_from(Key::Number(-9_007_199_254_740_991.0)),
+        Ok(-MAX_SAFE_INTEGER)
+    );
+    assert!(u64::try_from(Key::Number(9_007_199_254_740_992.0)).is_err());
+    assert!(u64::try_from(Key::Number(1e20)).is_err());
+
+    assert!(Key::try_from(JsValue::from_f64(f64::NAN)).is_err());
+    assert!(Key::try_from(JsValue::NULL).is_err());
+    assert!(Key::try_from(JsValue::from(js_sys::Object::new())).is_err());
+}